@@ -0,0 +1,12 @@
+CREATE TABLE invites (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code_hash TEXT NOT NULL UNIQUE,
+    role TEXT NOT NULL CHECK (role IN ('admin', 'director', 'teacher', 'student', 'parent')),
+    student_id UUID REFERENCES students(id) ON DELETE CASCADE,
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    redeemed_at TIMESTAMPTZ,
+    redeemed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    CHECK (student_id IS NULL OR role = 'parent')
+);
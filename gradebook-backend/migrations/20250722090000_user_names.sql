@@ -0,0 +1,8 @@
+-- The handlers have always read and written these columns, but no migration created them
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS first_name TEXT NOT NULL DEFAULT '',
+    ADD COLUMN IF NOT EXISTS last_name TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE users
+    ALTER COLUMN first_name DROP DEFAULT,
+    ALTER COLUMN last_name DROP DEFAULT;
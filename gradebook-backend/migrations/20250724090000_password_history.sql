@@ -0,0 +1,13 @@
+-- Hashes of each user's recent passwords, newest included, so they can't be reused
+CREATE TABLE password_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    hashed_password TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX password_history_user_id_idx ON password_history (user_id, created_at DESC);
+
+-- Start every existing user's history with their current password
+INSERT INTO password_history (user_id, hashed_password)
+SELECT id, hashed_password FROM users;
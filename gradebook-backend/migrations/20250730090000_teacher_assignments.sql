@@ -0,0 +1,12 @@
+-- Which teacher teaches which subject to which class. Teachers may only grade
+-- and record absences for students of classes they are assigned to.
+CREATE TABLE teacher_assignments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    teacher_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    class TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX teacher_assignments_unique_idx ON teacher_assignments (teacher_id, lower(class), lower(subject));
+CREATE INDEX teacher_assignments_class_idx ON teacher_assignments (lower(class));
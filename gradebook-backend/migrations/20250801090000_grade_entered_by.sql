@@ -0,0 +1,3 @@
+-- Who actually entered a grade when it differs from its author: set when an
+-- admin or director records a grade on a teacher's behalf.
+ALTER TABLE grades ADD COLUMN entered_by UUID REFERENCES users(id) ON DELETE SET NULL;
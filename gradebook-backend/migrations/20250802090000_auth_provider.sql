@@ -0,0 +1,3 @@
+-- Which provider checks an account's password at login. Accounts created on
+-- first login through a directory (LDAP) have no usable local password.
+ALTER TABLE users ADD COLUMN auth_provider TEXT NOT NULL DEFAULT 'local';
@@ -0,0 +1,10 @@
+-- A user can hold several roles, e.g. a teacher who is also a parent at the
+-- same school. Roles are kept in a fixed order (admin first, student last).
+ALTER TABLE users ADD COLUMN roles TEXT[];
+UPDATE users SET roles = ARRAY[role];
+ALTER TABLE users
+    ALTER COLUMN roles SET NOT NULL,
+    ADD CONSTRAINT users_roles_check CHECK (
+        cardinality(roles) > 0 AND roles <@ ARRAY['admin', 'director', 'teacher', 'parent', 'student']
+    );
+ALTER TABLE users DROP COLUMN role;
@@ -0,0 +1,29 @@
+-- Schools the gradebook serves. Users and students belong to (at most) one
+-- school; admins usually belong to none.
+CREATE TABLE schools (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    address TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX schools_name_idx ON schools (lower(name));
+
+-- A school can't be deleted while people still belong to it
+ALTER TABLE users ADD COLUMN school_id UUID REFERENCES schools(id) ON DELETE RESTRICT;
+ALTER TABLE students ADD COLUMN school_id UUID REFERENCES schools(id) ON DELETE RESTRICT;
+
+CREATE INDEX users_school_id_idx ON users (school_id);
+CREATE INDEX students_school_id_idx ON students (school_id);
+
+-- Existing installations served a single school: put everyone but the admins in it
+DO $$
+DECLARE
+    default_school UUID;
+BEGIN
+    IF EXISTS (SELECT 1 FROM users WHERE NOT ('admin' = ANY(roles))) THEN
+        INSERT INTO schools (name) VALUES ('Default school') RETURNING id INTO default_school;
+        UPDATE users SET school_id = default_school WHERE NOT ('admin' = ANY(roles));
+        UPDATE students SET school_id = default_school;
+    END IF;
+END $$;
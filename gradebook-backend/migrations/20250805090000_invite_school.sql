@@ -0,0 +1,4 @@
+-- The school an invited account will belong to; set from the inviter unless
+-- an admin picks one.
+ALTER TABLE invites ADD COLUMN school_id UUID REFERENCES schools(id) ON DELETE CASCADE;
+UPDATE invites i SET school_id = u.school_id FROM users u WHERE u.id = i.created_by;
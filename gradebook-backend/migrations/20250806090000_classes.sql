@@ -0,0 +1,68 @@
+-- Classes become records of their own instead of free text on students and
+-- teacher assignments ("10a", "10A" and "10 A" used to be three classes).
+CREATE TABLE classes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    school_id UUID NOT NULL REFERENCES schools(id) ON DELETE RESTRICT,
+    grade_level SMALLINT NOT NULL CHECK (grade_level BETWEEN 1 AND 12),
+    -- The parallel within the grade: "A", "B", ...
+    letter TEXT NOT NULL CHECK (letter <> '' AND letter = upper(letter)),
+    -- e.g. "2025/2026"
+    academic_year TEXT NOT NULL CHECK (academic_year ~ '^[0-9]{4}/[0-9]{4}$'),
+    homeroom_teacher_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX classes_unique_idx ON classes (school_id, academic_year, grade_level, letter);
+
+-- Convert the existing names. Whitespace and case are ignored; a name that
+-- isn't a grade level followed by letters ("10-A", "10A2") stops the migration,
+-- so it can be fixed by hand first. Everything is placed in the current academic year.
+CREATE TEMP TABLE legacy_classes AS
+    SELECT 'students' AS source, s.id AS row_id, s.school_id, upper(regexp_replace(s.class, '\s', '', 'g')) AS name
+    FROM students s
+    UNION ALL
+    SELECT 'teacher_assignments', ta.id, u.school_id, upper(regexp_replace(ta.class, '\s', '', 'g'))
+    FROM teacher_assignments ta JOIN users u ON u.id = ta.teacher_id;
+
+DO $$
+DECLARE
+    unconvertible TEXT;
+BEGIN
+    SELECT string_agg(DISTINCT coalesce(name, '') || CASE WHEN school_id IS NULL THEN ' (no school)' ELSE '' END, ', ')
+    INTO unconvertible
+    FROM legacy_classes
+    WHERE school_id IS NULL OR name !~ '^([1-9]|1[0-2])[[:alpha:]]+$';
+    IF unconvertible IS NOT NULL THEN
+        RAISE EXCEPTION 'Cannot convert these classes: %', unconvertible;
+    END IF;
+END $$;
+
+ALTER TABLE legacy_classes ADD COLUMN grade_level SMALLINT, ADD COLUMN letter TEXT;
+UPDATE legacy_classes SET
+    grade_level = substring(name FROM '^([0-9]+)')::smallint,
+    letter = substring(name FROM '^[0-9]+([[:alpha:]]+)$');
+
+INSERT INTO classes (school_id, grade_level, letter, academic_year)
+SELECT DISTINCT l.school_id, l.grade_level, l.letter, y.start_year || '/' || (y.start_year + 1)
+FROM legacy_classes l
+CROSS JOIN (SELECT extract(year FROM now() - interval '8 months')::int AS start_year) y;
+
+ALTER TABLE students ADD COLUMN class_id UUID REFERENCES classes(id) ON DELETE RESTRICT;
+UPDATE students s SET class_id = c.id
+FROM legacy_classes l JOIN classes c ON c.school_id = l.school_id AND c.grade_level = l.grade_level AND c.letter = l.letter
+WHERE l.source = 'students' AND l.row_id = s.id;
+ALTER TABLE students DROP COLUMN class;
+CREATE INDEX students_class_id_idx ON students (class_id);
+
+ALTER TABLE teacher_assignments ADD COLUMN class_id UUID REFERENCES classes(id) ON DELETE CASCADE;
+UPDATE teacher_assignments ta SET class_id = c.id
+FROM legacy_classes l JOIN classes c ON c.school_id = l.school_id AND c.grade_level = l.grade_level AND c.letter = l.letter
+WHERE l.source = 'teacher_assignments' AND l.row_id = ta.id;
+-- "10A" and "10 A" for the same teacher and subject are one assignment now
+DELETE FROM teacher_assignments a USING teacher_assignments b
+WHERE a.teacher_id = b.teacher_id AND a.class_id = b.class_id AND lower(a.subject) = lower(b.subject) AND a.id > b.id;
+ALTER TABLE teacher_assignments ALTER COLUMN class_id SET NOT NULL, DROP COLUMN class;
+CREATE UNIQUE INDEX teacher_assignments_unique_idx ON teacher_assignments (teacher_id, class_id, lower(subject));
+CREATE INDEX teacher_assignments_class_idx ON teacher_assignments (class_id);
+
+DROP TABLE legacy_classes;
@@ -0,0 +1,59 @@
+-- A catalog of subjects instead of free text on grades and teacher assignments
+-- ("Math", "Mathematics" and "математика" used to be three subjects).
+CREATE TABLE subjects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Short, stable identifier such as "MATH"
+    code TEXT NOT NULL CHECK (code ~ '^[[:alnum:]_]+$' AND code = upper(code)),
+    -- Name per language: {"en": "Mathematics", "bg": "Математика"}
+    names JSONB NOT NULL CHECK (jsonb_typeof(names) = 'object' AND names <> '{}'::jsonb),
+    -- Inactive subjects keep their grades but can't be given new ones
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX subjects_code_idx ON subjects (code);
+
+-- Every distinct free-text subject becomes a catalog entry (case ignored), named
+-- in Bulgarian if it is written in Cyrillic and in English otherwise. Codes come
+-- from the name without punctuation; different names that give the same code are
+-- numbered ("C++" is C, "C#" is C_2) and names without letters or digits are coded
+-- SUBJECT. Spelling variants end up as separate subjects; admins merge them
+-- afterwards (POST /subjects/:id/merge).
+CREATE TEMP TABLE legacy_subjects AS
+    SELECT name, coalesce(nullif(upper(trim(BOTH '_' FROM regexp_replace(name, '[^[:alnum:]]+', '_', 'g'))), ''), 'SUBJECT') AS code
+    FROM (
+        SELECT trim(subject) AS name FROM grades
+        UNION
+        SELECT trim(subject) FROM teacher_assignments
+    ) names;
+
+UPDATE legacy_subjects l SET code = l.code || '_' || n.rank
+FROM (
+    SELECT DISTINCT lower(name) AS name, dense_rank() OVER (PARTITION BY code ORDER BY lower(name)) AS rank
+    FROM legacy_subjects
+) n
+WHERE n.name = lower(l.name) AND n.rank > 1;
+
+INSERT INTO subjects (code, names)
+SELECT code, jsonb_build_object(CASE WHEN min(name) ~ '[А-Яа-я]' THEN 'bg' ELSE 'en' END, min(name))
+FROM legacy_subjects
+GROUP BY code;
+
+ALTER TABLE grades ADD COLUMN subject_id UUID REFERENCES subjects(id) ON DELETE RESTRICT;
+UPDATE grades g SET subject_id = sub.id
+FROM legacy_subjects l JOIN subjects sub ON sub.code = l.code
+WHERE l.name = trim(g.subject);
+ALTER TABLE grades ALTER COLUMN subject_id SET NOT NULL, DROP COLUMN subject;
+CREATE INDEX grades_subject_id_idx ON grades (subject_id);
+
+ALTER TABLE teacher_assignments ADD COLUMN subject_id UUID REFERENCES subjects(id) ON DELETE RESTRICT;
+UPDATE teacher_assignments ta SET subject_id = sub.id
+FROM legacy_subjects l JOIN subjects sub ON sub.code = l.code
+WHERE l.name = trim(ta.subject);
+-- "Math" and "math" for the same teacher and class are one assignment now
+DELETE FROM teacher_assignments a USING teacher_assignments b
+WHERE a.teacher_id = b.teacher_id AND a.class_id = b.class_id AND a.subject_id = b.subject_id AND a.id > b.id;
+ALTER TABLE teacher_assignments ALTER COLUMN subject_id SET NOT NULL, DROP COLUMN subject;
+CREATE UNIQUE INDEX teacher_assignments_unique_idx ON teacher_assignments (teacher_id, class_id, subject_id);
+
+DROP TABLE legacy_subjects;
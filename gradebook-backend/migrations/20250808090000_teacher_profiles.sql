@@ -0,0 +1,24 @@
+-- Personal data of teachers beyond their account, and the subjects they are
+-- qualified to teach. Teachers without a profile row simply have no data yet.
+CREATE TABLE teacher_profiles (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    phone TEXT,
+    address TEXT,
+    date_of_birth DATE,
+    hired_on DATE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE teacher_qualifications (
+    teacher_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    subject_id UUID NOT NULL REFERENCES subjects(id) ON DELETE RESTRICT,
+    PRIMARY KEY (teacher_id, subject_id)
+);
+
+-- Grade entry now requires a qualification, so teachers keep the subjects they
+-- already teach or have graded in.
+INSERT INTO teacher_qualifications (teacher_id, subject_id)
+SELECT teacher_id, subject_id FROM teacher_assignments
+UNION
+SELECT teacher_id, subject_id FROM grades
+ON CONFLICT DO NOTHING;
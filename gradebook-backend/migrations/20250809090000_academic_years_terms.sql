@@ -0,0 +1,56 @@
+-- The school calendar: academic years split into terms. Grades and absences
+-- belong to the term their date falls in, so figures can be asked per term or
+-- per year. Years of a school don't overlap and terms lie within their year
+-- without overlapping each other (checked by the API).
+CREATE TABLE academic_years (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    school_id UUID NOT NULL REFERENCES schools(id) ON DELETE CASCADE,
+    -- e.g. "2025/2026"
+    name TEXT NOT NULL CHECK (name ~ '^\d{4}/\d{4}$'),
+    starts_on DATE NOT NULL,
+    ends_on DATE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (starts_on < ends_on)
+);
+
+CREATE UNIQUE INDEX academic_years_name_idx ON academic_years (school_id, name);
+-- Lets classes refer to a year of their own school
+CREATE UNIQUE INDEX academic_years_school_idx ON academic_years (id, school_id);
+
+-- Classes named their year so far; each of those years becomes a record running
+-- from September 1 to August 31 (admins adjust the dates if they differ). A year
+-- can't be deleted while it has classes.
+INSERT INTO academic_years (school_id, name, starts_on, ends_on)
+SELECT DISTINCT school_id, academic_year,
+       make_date(split_part(academic_year, '/', 1)::int, 9, 1),
+       make_date(split_part(academic_year, '/', 2)::int, 8, 31)
+FROM classes;
+
+ALTER TABLE classes ADD COLUMN academic_year_id UUID;
+UPDATE classes c SET academic_year_id = y.id
+FROM academic_years y
+WHERE y.school_id = c.school_id AND y.name = c.academic_year;
+ALTER TABLE classes
+    ALTER COLUMN academic_year_id SET NOT NULL,
+    ADD FOREIGN KEY (academic_year_id, school_id) REFERENCES academic_years (id, school_id) ON DELETE RESTRICT,
+    DROP COLUMN academic_year;
+CREATE UNIQUE INDEX classes_unique_idx ON classes (academic_year_id, grade_level, letter);
+
+CREATE TABLE terms (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    academic_year_id UUID NOT NULL REFERENCES academic_years(id) ON DELETE CASCADE,
+    name TEXT NOT NULL CHECK (name <> ''),
+    starts_on DATE NOT NULL,
+    ends_on DATE NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (starts_on <= ends_on)
+);
+
+CREATE UNIQUE INDEX terms_name_idx ON terms (academic_year_id, name);
+
+-- Records dated outside every term (holidays, years nobody set up) have none
+ALTER TABLE grades ADD COLUMN term_id UUID REFERENCES terms(id) ON DELETE SET NULL;
+ALTER TABLE absences ADD COLUMN term_id UUID REFERENCES terms(id) ON DELETE SET NULL;
+
+CREATE INDEX grades_term_id_idx ON grades (term_id);
+CREATE INDEX absences_term_id_idx ON absences (term_id);
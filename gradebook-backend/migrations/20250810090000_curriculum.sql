@@ -0,0 +1,55 @@
+-- The curriculum: which subjects a class has in a term, how many hours a week,
+-- and who teaches them. It replaces teacher_assignments as the record of who
+-- teaches whom.
+CREATE TABLE curriculum_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    class_id UUID NOT NULL REFERENCES classes(id) ON DELETE CASCADE,
+    term_id UUID NOT NULL REFERENCES terms(id) ON DELETE CASCADE,
+    subject_id UUID NOT NULL REFERENCES subjects(id) ON DELETE RESTRICT,
+    -- NULL only for entries carried over from teacher assignments, which had no hours
+    weekly_hours SMALLINT CHECK (weekly_hours BETWEEN 1 AND 40),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX curriculum_entries_unique_idx ON curriculum_entries (class_id, term_id, subject_id);
+
+-- A subject may be taught by several teachers (e.g. groups in languages)
+CREATE TABLE curriculum_teachers (
+    entry_id UUID NOT NULL REFERENCES curriculum_entries(id) ON DELETE CASCADE,
+    teacher_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    PRIMARY KEY (entry_id, teacher_id)
+);
+
+CREATE INDEX curriculum_teachers_teacher_id_idx ON curriculum_teachers (teacher_id);
+
+-- Each teacher assignment becomes an entry in every term of the class's
+-- academic year. Years without terms get a single term spanning the whole year;
+-- admins can shorten or split it later and copy the curriculum into new terms.
+INSERT INTO terms (academic_year_id, name, starts_on, ends_on)
+SELECT y.id, 'Whole year', y.starts_on, y.ends_on
+FROM academic_years y
+WHERE EXISTS (SELECT 1 FROM teacher_assignments ta JOIN classes c ON c.id = ta.class_id WHERE c.academic_year_id = y.id)
+  AND NOT EXISTS (SELECT 1 FROM terms t WHERE t.academic_year_id = y.id);
+
+-- Grades and absences dated in those years belong to the new terms
+UPDATE grades g SET term_id = t.id
+FROM students s, academic_years y JOIN terms t ON t.academic_year_id = y.id
+WHERE s.id = g.student_id AND y.school_id = s.school_id AND g.term_id IS NULL
+  AND (g.created_at AT TIME ZONE 'UTC')::date BETWEEN t.starts_on AND t.ends_on;
+UPDATE absences a SET term_id = t.id
+FROM students s, academic_years y JOIN terms t ON t.academic_year_id = y.id
+WHERE s.id = a.student_id AND y.school_id = s.school_id AND a.term_id IS NULL
+  AND a.date BETWEEN t.starts_on AND t.ends_on;
+
+INSERT INTO curriculum_entries (class_id, term_id, subject_id)
+SELECT DISTINCT ta.class_id, t.id, ta.subject_id
+FROM teacher_assignments ta
+JOIN classes c ON c.id = ta.class_id
+JOIN terms t ON t.academic_year_id = c.academic_year_id;
+
+INSERT INTO curriculum_teachers (entry_id, teacher_id)
+SELECT ce.id, ta.teacher_id
+FROM teacher_assignments ta JOIN curriculum_entries ce ON ce.class_id = ta.class_id AND ce.subject_id = ta.subject_id
+ON CONFLICT DO NOTHING;
+
+DROP TABLE teacher_assignments;
@@ -0,0 +1,3 @@
+-- A key can be limited to one school. Such a key acts as a director of that
+-- school rather than as the admin who created it.
+ALTER TABLE api_keys ADD COLUMN school_id UUID REFERENCES schools(id) ON DELETE CASCADE;
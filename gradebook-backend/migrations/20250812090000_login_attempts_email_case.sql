@@ -0,0 +1,6 @@
+-- Login attempts are counted per email regardless of case, so they are stored
+-- trimmed and in lower case, and indexed for counting recent failures.
+UPDATE login_attempts SET email = lower(trim(email)) WHERE email <> lower(trim(email));
+
+DROP INDEX login_attempts_email_idx;
+CREATE INDEX login_attempts_email_failures_idx ON login_attempts (email, attempted_at) WHERE NOT succeeded;
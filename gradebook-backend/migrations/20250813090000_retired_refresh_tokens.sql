@@ -0,0 +1,9 @@
+-- Refresh tokens that were swapped for a new one. Presenting one again means
+-- someone else holds a copy, so the session it belonged to is revoked.
+CREATE TABLE retired_refresh_tokens (
+    token_hash TEXT PRIMARY KEY,
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    retired_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX retired_refresh_tokens_session_id_idx ON retired_refresh_tokens (session_id);
@@ -0,0 +1,73 @@
+use axum::http::Method;
+
+use crate::auth::{generate_token, hash_token};
+
+// Prefix that tells API keys apart from JWT access tokens in the Authorization header.
+pub const KEY_PREFIX: &str = "gbk_";
+
+pub const DEFAULT_TTL_DAYS: i64 = 90;
+pub const MAX_TTL_DAYS: i64 = 365;
+
+// Resources an API key can be granted access to, each as "<resource>:read"
+// (GET requests) and/or "<resource>:write" (everything else). The resource is
+// the first segment of the route, so "grades:read" covers GET /grades. User
+// management is left out on purpose: it reaches sessions, TOTP and admin
+// accounts, which need a human login.
+const RESOURCES: &[&str] = &["students", "grades", "absences", "parent_students", "curriculum", "stats", "schools", "classes", "subjects", "teachers", "academic_years", "terms"];
+
+pub fn generate_key() -> String {
+    format!("{}{}", KEY_PREFIX, generate_token())
+}
+
+pub fn hash_key(key: &str) -> String {
+    hash_token(key)
+}
+
+pub fn is_api_key(token: &str) -> bool {
+    token.starts_with(KEY_PREFIX)
+}
+
+pub fn is_valid_scope(scope: &str) -> bool {
+    scope
+        .split_once(':')
+        .is_some_and(|(resource, access)| RESOURCES.contains(&resource) && matches!(access, "read" | "write"))
+}
+
+// Scope a request needs, from its method and matched route ("/grades/:id" -> "grades:write" for PUT).
+pub fn required_scope(method: &Method, route: &str) -> Option<String> {
+    let resource = route.trim_start_matches('/').split('/').next()?;
+    if !RESOURCES.contains(&resource) {
+        return None;
+    }
+    let access = if *method == Method::GET { "read" } else { "write" };
+    Some(format!("{}:{}", resource, access))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scope_follows_method_and_first_route_segment() {
+        assert_eq!(required_scope(&Method::GET, "/grades/:id").as_deref(), Some("grades:read"));
+        assert_eq!(required_scope(&Method::DELETE, "/grades/:id").as_deref(), Some("grades:write"));
+        assert_eq!(required_scope(&Method::POST, "/curriculum/copy").as_deref(), Some("curriculum:write"));
+    }
+
+    #[test]
+    fn user_management_is_out_of_reach() {
+        for route in ["/users", "/users/:id", "/users/:id/totp", "/users/:id/sessions", "/users/:id/unlock"] {
+            assert_eq!(required_scope(&Method::DELETE, route), None, "{}", route);
+            assert_eq!(required_scope(&Method::GET, route), None, "{}", route);
+        }
+        assert!(!is_valid_scope("users:write"));
+        assert!(!is_valid_scope("users:read"));
+    }
+
+    #[test]
+    fn api_key_management_and_sessions_are_out_of_reach() {
+        for route in ["/api_keys", "/me", "/auth/totp", "/auth/logout"] {
+            assert_eq!(required_scope(&Method::POST, route), None, "{}", route);
+        }
+    }
+}
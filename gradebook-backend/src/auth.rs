@@ -0,0 +1,260 @@
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::models::user::{Role, User};
+
+const ISSUER: &str = "gradebook-backend";
+const AUDIENCE: &str = "gradebook-api";
+// Second-factor challenge tokens use their own audience so they can never be
+// presented as access tokens (and vice versa).
+const MFA_AUDIENCE: &str = "gradebook-mfa";
+const MFA_TOKEN_TTL_MINUTES: i64 = 5;
+const MIN_SECRET_LEN: usize = 32;
+const OPAQUE_TOKEN_LEN: usize = 48;
+
+// Claims carried by every access token. `roles` is checked against the DB on
+// each request so a role change invalidates tokens issued before it, and `sid`
+// ties the token to a row in `sessions` so it can be revoked server-side.
+// `act` is set on impersonation tokens to the admin acting as `sub`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: Uuid,
+    pub sid: Uuid,
+    pub roles: Vec<Role>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Uuid>,
+    pub iss: String,
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+// Claims of the short-lived token handed out after a correct password when a
+// TOTP code is still required.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaClaims {
+    pub sub: Uuid,
+    pub iss: String,
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+#[derive(Clone)]
+pub struct AuthConfig {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    pub access_ttl: Duration,
+    pub refresh_ttl: Duration,
+}
+
+impl AuthConfig {
+    pub fn from_env() -> Self {
+        let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set in .env");
+        let ttl_minutes = std::env::var("JWT_ACCESS_TTL_MINUTES")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(15);
+        let refresh_ttl_days = std::env::var("JWT_REFRESH_TTL_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(14);
+
+        AuthConfig::new(&secret, Duration::minutes(ttl_minutes), Duration::days(refresh_ttl_days))
+    }
+
+    // Panics on a secret too short to sign tokens safely
+    pub fn new(secret: &str, access_ttl: Duration, refresh_ttl: Duration) -> Self {
+        if secret.len() < MIN_SECRET_LEN {
+            panic!("JWT_SECRET must be at least {} bytes long", MIN_SECRET_LEN);
+        }
+        AuthConfig {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            access_ttl,
+            refresh_ttl,
+        }
+    }
+
+    pub fn issue_access_token(&self, user: &User, session_id: Uuid) -> Result<String, jsonwebtoken::errors::Error> {
+        self.issue(user, session_id, None, Utc::now() + self.access_ttl)
+    }
+
+    // Access token for an admin viewing the app as `user`, valid for the whole
+    // (non-refreshable) impersonation session.
+    pub fn issue_impersonation_token(&self, user: &User, session_id: Uuid, impersonator_id: Uuid, expires_at: DateTime<Utc>)
+        -> Result<String, jsonwebtoken::errors::Error>
+    {
+        self.issue(user, session_id, Some(impersonator_id), expires_at)
+    }
+
+    fn issue(&self, user: &User, session_id: Uuid, act: Option<Uuid>, expires_at: DateTime<Utc>)
+        -> Result<String, jsonwebtoken::errors::Error>
+    {
+        let claims = Claims {
+            sub: user.id,
+            sid: session_id,
+            roles: user.roles.clone(),
+            act,
+            iss: ISSUER.to_string(),
+            aud: AUDIENCE.to_string(),
+            iat: Utc::now().timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
+    }
+
+    pub fn verify_access_token(&self, token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[ISSUER]);
+        validation.set_audience(&[AUDIENCE]);
+        validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
+        decode::<Claims>(token, &self.decoding_key, &validation).map(|data| data.claims)
+    }
+
+    pub fn mfa_token_ttl(&self) -> Duration {
+        Duration::minutes(MFA_TOKEN_TTL_MINUTES)
+    }
+
+    pub fn issue_mfa_token(&self, user_id: Uuid) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = Utc::now();
+        let claims = MfaClaims {
+            sub: user_id,
+            iss: ISSUER.to_string(),
+            aud: MFA_AUDIENCE.to_string(),
+            iat: now.timestamp(),
+            exp: (now + self.mfa_token_ttl()).timestamp(),
+        };
+        encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
+    }
+
+    pub fn verify_mfa_token(&self, token: &str) -> Result<MfaClaims, jsonwebtoken::errors::Error> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[ISSUER]);
+        validation.set_audience(&[MFA_AUDIENCE]);
+        validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
+        decode::<MfaClaims>(token, &self.decoding_key, &validation).map(|data| data.claims)
+    }
+}
+
+// Pulls the token out of an `Authorization: Bearer <token>` header value.
+pub fn bearer_token(header: &str) -> Option<&str> {
+    let (scheme, token) = header.split_once(' ')?;
+    if scheme.eq_ignore_ascii_case("bearer") && !token.trim().is_empty() {
+        Some(token.trim())
+    } else {
+        None
+    }
+}
+
+// Random opaque token handed to clients (refresh tokens etc.). Only its hash is stored.
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(OPAQUE_TOKEN_LEN)
+        .map(char::from)
+        .collect()
+}
+
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::user;
+
+    const SECRET: &str = "a test secret that is long enough to sign with";
+
+    fn config() -> AuthConfig {
+        AuthConfig::new(SECRET, Duration::minutes(15), Duration::days(14))
+    }
+
+    // A token with the given claims, signed with `secret`
+    fn forged(secret: &str, iss: &str, aud: &str, exp: DateTime<Utc>) -> String {
+        let claims = Claims {
+            sub: Uuid::new_v4(),
+            sid: Uuid::new_v4(),
+            roles: vec![Role::Admin],
+            act: None,
+            iss: iss.to_string(),
+            aud: aud.to_string(),
+            iat: Utc::now().timestamp(),
+            exp: exp.timestamp(),
+        };
+        encode(&Header::new(Algorithm::HS256), &claims, &EncodingKey::from_secret(secret.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn access_tokens_round_trip() {
+        let config = config();
+        let user = user(&[Role::Teacher, Role::Parent], None);
+        let session_id = Uuid::new_v4();
+        let claims = config.verify_access_token(&config.issue_access_token(&user, session_id).unwrap()).unwrap();
+        assert_eq!((claims.sub, claims.sid, &claims.roles, claims.act), (user.id, session_id, &user.roles, None));
+
+        let admin_id = Uuid::new_v4();
+        let token = config.issue_impersonation_token(&user, session_id, admin_id, Utc::now() + Duration::minutes(30)).unwrap();
+        assert_eq!(config.verify_access_token(&token).unwrap().act, Some(admin_id));
+    }
+
+    #[test]
+    fn mfa_and_access_tokens_are_not_interchangeable() {
+        let config = config();
+        let user = user(&[Role::Director], None);
+        let mfa_token = config.issue_mfa_token(user.id).unwrap();
+        assert_eq!(config.verify_mfa_token(&mfa_token).unwrap().sub, user.id);
+        assert!(config.verify_access_token(&mfa_token).is_err());
+
+        let access_token = config.issue_access_token(&user, Uuid::new_v4()).unwrap();
+        assert!(config.verify_mfa_token(&access_token).is_err());
+    }
+
+    #[test]
+    fn foreign_and_expired_tokens_are_rejected() {
+        let config = config();
+        let later = Utc::now() + Duration::minutes(5);
+        assert!(config.verify_access_token(&forged(SECRET, ISSUER, AUDIENCE, later)).is_ok());
+        for token in [
+            forged(SECRET, "someone-else", AUDIENCE, later),
+            forged(SECRET, ISSUER, "another-api", later),
+            forged("another secret that is just as long as ours", ISSUER, AUDIENCE, later),
+            // Past the default leeway of a minute
+            forged(SECRET, ISSUER, AUDIENCE, Utc::now() - Duration::minutes(2)),
+        ] {
+            assert!(config.verify_access_token(&token).is_err());
+        }
+        let user = user(&[Role::Teacher], None);
+        let expired = config.issue_impersonation_token(&user, Uuid::new_v4(), Uuid::new_v4(), Utc::now() - Duration::minutes(2)).unwrap();
+        assert!(config.verify_access_token(&expired).is_err());
+        assert!(config.verify_access_token("not.a.token").is_err());
+    }
+
+    #[test]
+    fn bearer_tokens_are_parsed() {
+        for (header, expected) in [
+            ("Bearer abc.def", Some("abc.def")),
+            ("bearer abc.def", Some("abc.def")),
+            ("BEARER  abc.def ", Some("abc.def")),
+            ("Bearer gbk_key", Some("gbk_key")),
+            ("Bearer ", None),
+            ("Bearer", None),
+            ("Basic dXNlcjpwYXNz", None),
+            ("abc.def", None),
+            ("", None),
+        ] {
+            assert_eq!(bearer_token(header), expected, "{:?}", header);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "JWT_SECRET must be at least 32 bytes long")]
+    fn short_secrets_are_refused() {
+        AuthConfig::new("too short", Duration::minutes(15), Duration::days(14));
+    }
+}
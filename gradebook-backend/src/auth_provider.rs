@@ -0,0 +1,579 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ldap3::{ldap_escape, Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::user::{Role, User};
+use crate::password::{self, PasswordError};
+
+// Values of `users.auth_provider`: which provider checks an account's password.
+pub const LOCAL: &str = "local";
+pub const LDAP: &str = "ldap";
+
+// Stored as the password hash of accounts that log in through a directory. It
+// never parses as a hash, so such accounts can't log in with a local password.
+const NO_LOCAL_PASSWORD: &str = "!directory";
+
+// LDAP result code for a failed simple bind
+const INVALID_CREDENTIALS: u32 = 49;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuthProviderError {
+    #[error(transparent)]
+    Password(#[from] PasswordError),
+    #[error(transparent)]
+    Db(#[from] sqlx::Error),
+    #[error("directory error: {0}")]
+    Directory(String),
+}
+
+impl From<ldap3::LdapError> for AuthProviderError {
+    fn from(e: ldap3::LdapError) -> Self {
+        AuthProviderError::Directory(e.to_string())
+    }
+}
+
+// Checks login credentials. `login` finds the account for the email (if any) and
+// applies lockout first, then hands over to the provider the account belongs to,
+// or to the primary provider when the email is unknown.
+#[axum::async_trait]
+pub trait AuthProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // The authenticated account, or None for wrong credentials. Providers backed
+    // by a directory create the account on first login and keep it in sync.
+    async fn authenticate(&self, pool: &PgPool, email: &str, password: &str, user: Option<&User>)
+        -> Result<Option<User>, AuthProviderError>;
+}
+
+// Password hashes in our own `users` table.
+pub struct LocalProvider;
+
+#[axum::async_trait]
+impl AuthProvider for LocalProvider {
+    fn name(&self) -> &'static str {
+        LOCAL
+    }
+
+    async fn authenticate(&self, pool: &PgPool, _email: &str, password: &str, user: Option<&User>)
+        -> Result<Option<User>, AuthProviderError>
+    {
+        // Unknown emails still pay for a hash verification so they can't be told apart by timing
+        let Some(user) = user else {
+            password::verify_nothing(password).await?;
+            return Ok(None);
+        };
+        let valid = password::verify_user(pool, user.id, password).await?;
+        Ok(valid.then(|| user.clone()))
+    }
+}
+
+// A person's entry as returned by a directory search.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DirectoryEntry {
+    pub dn: String,
+    #[serde(default)]
+    pub attributes: HashMap<String, Vec<String>>,
+}
+
+impl DirectoryEntry {
+    // Attribute names are case-insensitive in LDAP
+    fn values(&self, attribute: &str) -> &[String] {
+        self.attributes
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(attribute))
+            .map_or(&[], |(_, values)| values.as_slice())
+    }
+
+    fn first(&self, attribute: &str) -> Option<&str> {
+        self.values(attribute).first().map(String::as_str)
+    }
+}
+
+// The two directory operations a login needs, so the provider can run against a
+// real server or the in-process fake.
+#[axum::async_trait]
+pub trait Directory: Send + Sync {
+    // The entry for this email, or None unless exactly one entry matches
+    async fn find_by_email(&self, email: &str) -> Result<Option<DirectoryEntry>, AuthProviderError>;
+
+    // Whether the password is correct for the entry (a simple bind as its DN)
+    async fn bind(&self, dn: &str, password: &str) -> Result<bool, AuthProviderError>;
+}
+
+pub struct LdapDirectory {
+    url: String,
+    // Service account used for the search; anonymous when unset
+    search_bind: Option<(String, String)>,
+    base_dn: String,
+    // Search filter with `{email}` in place of the (escaped) login email
+    user_filter: String,
+    attributes: Vec<String>,
+    timeout: Duration,
+}
+
+impl LdapDirectory {
+    async fn connect(&self) -> Result<Ldap, AuthProviderError> {
+        let settings = LdapConnSettings::new().set_conn_timeout(self.timeout);
+        let (conn, ldap) = LdapConnAsync::with_settings(settings, &self.url).await?;
+        ldap3::drive!(conn);
+        Ok(ldap)
+    }
+}
+
+#[axum::async_trait]
+impl Directory for LdapDirectory {
+    async fn find_by_email(&self, email: &str) -> Result<Option<DirectoryEntry>, AuthProviderError> {
+        let mut ldap = self.connect().await?;
+        if let Some((dn, password)) = &self.search_bind {
+            ldap.simple_bind(dn, password).await?.success()?;
+        }
+
+        let filter = self.user_filter.replace("{email}", &ldap_escape(email));
+        let (entries, _) = ldap
+            .search(&self.base_dn, Scope::Subtree, &filter, &self.attributes)
+            .await?
+            .success()?;
+        let _ = ldap.unbind().await;
+
+        if entries.len() > 1 {
+            tracing::warn!(%email, matches = entries.len(), "directory login refused: email matches several entries");
+            return Ok(None);
+        }
+        Ok(entries.into_iter().next().map(|entry| {
+            let entry = SearchEntry::construct(entry);
+            DirectoryEntry { dn: entry.dn, attributes: entry.attrs }
+        }))
+    }
+
+    async fn bind(&self, dn: &str, password: &str) -> Result<bool, AuthProviderError> {
+        let mut ldap = self.connect().await?;
+        let result = ldap.simple_bind(dn, password).await?;
+        let _ = ldap.unbind().await;
+        match result.rc {
+            0 => Ok(true),
+            INVALID_CREDENTIALS => Ok(false),
+            _ => Err(ldap3::LdapError::from(result).into()),
+        }
+    }
+}
+
+// In-process stand-in for a directory server, loaded from a JSON file of
+// entries (`dn`, `password`, `attributes`). For development and tests only.
+pub struct FakeDirectory {
+    entries: Vec<FakeEntry>,
+    email_attribute: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FakeEntry {
+    #[serde(flatten)]
+    pub entry: DirectoryEntry,
+    pub password: String,
+}
+
+impl FakeDirectory {
+    pub fn new(entries: Vec<FakeEntry>, email_attribute: impl Into<String>) -> Self {
+        FakeDirectory { entries, email_attribute: email_attribute.into() }
+    }
+}
+
+#[axum::async_trait]
+impl Directory for FakeDirectory {
+    async fn find_by_email(&self, email: &str) -> Result<Option<DirectoryEntry>, AuthProviderError> {
+        let mut matches = self.entries.iter().filter(|fake| {
+            fake.entry.values(&self.email_attribute).iter().any(|value| value.eq_ignore_ascii_case(email))
+        });
+        Ok(match (matches.next(), matches.next()) {
+            (Some(fake), None) => Some(fake.entry.clone()),
+            _ => None,
+        })
+    }
+
+    async fn bind(&self, dn: &str, password: &str) -> Result<bool, AuthProviderError> {
+        Ok(self.entries.iter().any(|fake| fake.entry.dn.eq_ignore_ascii_case(dn) && fake.password == password))
+    }
+}
+
+// How directory attributes become a `users` row.
+pub struct AttributeMapping {
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub role: String,
+    // Every value found in the role attribute grants its role
+    pub roles: Vec<(String, Role)>,
+    // Role for people none of `roles` match; without one they can't log in
+    pub default_role: Option<Role>,
+    // Attribute naming the person's school (matched against school names)
+    pub school: Option<String>,
+    // School for people whose entry names none we know
+    pub default_school_id: Option<Uuid>,
+}
+
+impl AttributeMapping {
+    // Empty if the entry maps to no role and there is no default
+    pub fn roles_for(&self, entry: &DirectoryEntry) -> Vec<Role> {
+        let values = entry.values(&self.role);
+        let roles: Vec<Role> = self.roles
+            .iter()
+            .filter(|(value, _)| values.iter().any(|v| v.eq_ignore_ascii_case(value)))
+            .map(|(_, role)| role.clone())
+            .collect();
+        if roles.is_empty() {
+            self.default_role.iter().cloned().collect()
+        } else {
+            Role::normalize(roles)
+        }
+    }
+}
+
+// Binds as the person's directory entry and creates or updates their account
+// from its attributes.
+pub struct LdapProvider {
+    directory: Box<dyn Directory>,
+    mapping: AttributeMapping,
+}
+
+impl LdapProvider {
+    pub fn new(directory: Box<dyn Directory>, mapping: AttributeMapping) -> Self {
+        LdapProvider { directory, mapping }
+    }
+
+    // The school named in the entry, or else the configured default
+    async fn school_for(&self, pool: &PgPool, entry: &DirectoryEntry) -> Result<Option<Uuid>, AuthProviderError> {
+        if let Some(name) = self.mapping.school.as_deref().and_then(|attribute| entry.first(attribute)) {
+            let school_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM schools WHERE lower(name) = lower($1)")
+                .bind(name.trim())
+                .fetch_optional(pool)
+                .await?;
+            if school_id.is_some() {
+                return Ok(school_id);
+            }
+            tracing::warn!(dn = %entry.dn, school = %name, "directory entry names an unknown school");
+        }
+        Ok(self.mapping.default_school_id)
+    }
+}
+
+#[axum::async_trait]
+impl AuthProvider for LdapProvider {
+    fn name(&self) -> &'static str {
+        LDAP
+    }
+
+    async fn authenticate(&self, pool: &PgPool, email: &str, password: &str, user: Option<&User>)
+        -> Result<Option<User>, AuthProviderError>
+    {
+        // An empty password would be an unauthenticated bind, which servers accept
+        if password.is_empty() {
+            return Ok(None);
+        }
+        let Some(entry) = self.directory.find_by_email(email).await? else {
+            return Ok(None);
+        };
+        if !self.directory.bind(&entry.dn, password).await? {
+            return Ok(None);
+        }
+        let roles = self.mapping.roles_for(&entry);
+        if roles.is_empty() {
+            tracing::warn!(%email, dn = %entry.dn, "directory login refused: no role mapped for entry");
+            return Ok(None);
+        }
+        let first_name = entry.first(&self.mapping.first_name).unwrap_or_default();
+        let last_name = entry.first(&self.mapping.last_name).unwrap_or_default();
+        let school_id = self.school_for(pool, &entry).await?;
+        if school_id.is_none() && !roles.contains(&Role::Admin) {
+            tracing::warn!(%email, dn = %entry.dn, "directory entry maps to no school; the account won't see any school data");
+        }
+
+        // A school the directory no longer names is kept, as admins may have set it
+        let account = match user {
+            Some(user) => {
+                sqlx::query_as::<_, User>(
+                    r#"
+                    UPDATE users SET roles = $2, first_name = $3, last_name = $4, school_id = COALESCE($5, school_id)
+                    WHERE id = $1
+                    RETURNING *
+                    "#
+                )
+                .bind(user.id)
+                .bind(&roles)
+                .bind(first_name)
+                .bind(last_name)
+                .bind(school_id)
+                .fetch_one(pool)
+                .await?
+            }
+            None => {
+                // Stored as the directory spells it, not as typed at login
+                let email = entry.first(&self.mapping.email).unwrap_or(email);
+                let created = sqlx::query_as::<_, User>(
+                    r#"
+                    INSERT INTO users (email, hashed_password, roles, first_name, last_name, auth_provider, school_id)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    RETURNING *
+                    "#
+                )
+                .bind(email)
+                .bind(NO_LOCAL_PASSWORD)
+                .bind(&roles)
+                .bind(first_name)
+                .bind(last_name)
+                .bind(LDAP)
+                .bind(school_id)
+                .fetch_one(pool)
+                .await?;
+                tracing::info!(user_id = %created.id, %email, roles = ?created.roles, "account created from directory");
+                created
+            }
+        };
+        Ok(Some(account))
+    }
+}
+
+// The configured providers; the first one handles logins for unknown emails.
+#[derive(Clone)]
+pub struct AuthProviders {
+    providers: Arc<Vec<Arc<dyn AuthProvider>>>,
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+fn parse_role(value: &str) -> Role {
+    serde_json::from_value(serde_json::Value::String(value.trim().to_lowercase()))
+        .unwrap_or_else(|_| panic!("Unknown role in LDAP configuration: {}", value))
+}
+
+// LDAP_ROLE_MAP: `value:role` pairs separated by `;`, e.g.
+// `cn=teachers,ou=groups,dc=school,dc=org:teacher;cn=staff,ou=groups,dc=school,dc=org:director`
+fn parse_role_map(map: &str) -> Vec<(String, Role)> {
+    map.split(';')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (value, role) = pair
+                .rsplit_once(':')
+                .unwrap_or_else(|| panic!("LDAP_ROLE_MAP entry must be value:role, got {}", pair));
+            (value.trim().to_string(), parse_role(role))
+        })
+        .collect()
+}
+
+fn ldap_from_env() -> LdapProvider {
+    let var = |key: &str, default: &str| std::env::var(key).unwrap_or_else(|_| default.to_string());
+    let mapping = AttributeMapping {
+        email: var("LDAP_EMAIL_ATTRIBUTE", "mail"),
+        first_name: var("LDAP_FIRST_NAME_ATTRIBUTE", "givenName"),
+        last_name: var("LDAP_LAST_NAME_ATTRIBUTE", "sn"),
+        role: var("LDAP_ROLE_ATTRIBUTE", "memberOf"),
+        roles: parse_role_map(&var("LDAP_ROLE_MAP", "")),
+        default_role: std::env::var("LDAP_DEFAULT_ROLE").ok().map(|role| parse_role(&role)),
+        school: std::env::var("LDAP_SCHOOL_ATTRIBUTE").ok(),
+        default_school_id: std::env::var("LDAP_SCHOOL_ID").ok().map(|id| {
+            id.parse().unwrap_or_else(|_| panic!("LDAP_SCHOOL_ID must be a school id, got {}", id))
+        }),
+    };
+
+    let directory: Box<dyn Directory> = match std::env::var("LDAP_FAKE_DIRECTORY") {
+        Ok(path) => {
+            let contents = std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("LDAP_FAKE_DIRECTORY {} could not be read: {}", path, e));
+            let entries: Vec<FakeEntry> = serde_json::from_str(&contents)
+                .unwrap_or_else(|e| panic!("LDAP_FAKE_DIRECTORY {} is not a valid entry list: {}", path, e));
+            tracing::warn!(%path, entries = entries.len(), "using fake LDAP directory");
+            Box::new(FakeDirectory::new(entries, mapping.email.clone()))
+        }
+        Err(_) => Box::new(LdapDirectory {
+            url: std::env::var("LDAP_URL").expect("LDAP_URL must be set when AUTH_PROVIDERS includes ldap"),
+            search_bind: std::env::var("LDAP_BIND_DN").ok().map(|dn| (dn, var("LDAP_BIND_PASSWORD", ""))),
+            base_dn: std::env::var("LDAP_BASE_DN").expect("LDAP_BASE_DN must be set when AUTH_PROVIDERS includes ldap"),
+            user_filter: var("LDAP_USER_FILTER", "(&(objectClass=person)(mail={email}))"),
+            attributes: [
+                Some(&mapping.email),
+                Some(&mapping.first_name),
+                Some(&mapping.last_name),
+                Some(&mapping.role),
+                mapping.school.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect(),
+            timeout: Duration::from_secs(env_or("LDAP_TIMEOUT_SECONDS", 5)),
+        }),
+    };
+
+    LdapProvider::new(directory, mapping)
+}
+
+impl AuthProviders {
+    // AUTH_PROVIDERS: comma-separated list of `local` and `ldap`, default `local`
+    pub fn from_env() -> Self {
+        let providers: Vec<Arc<dyn AuthProvider>> = std::env::var("AUTH_PROVIDERS")
+            .unwrap_or_else(|_| LOCAL.to_string())
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| -> Arc<dyn AuthProvider> {
+                match name {
+                    LOCAL => Arc::new(LocalProvider),
+                    LDAP => Arc::new(ldap_from_env()),
+                    other => panic!("Unknown auth provider: {}", other),
+                }
+            })
+            .collect();
+        if providers.is_empty() {
+            panic!("AUTH_PROVIDERS must name at least one provider");
+        }
+        AuthProviders { providers: Arc::new(providers) }
+    }
+
+    // The provider that checks this account's password, or the primary one for an
+    // unknown email. None if the account's provider is not configured.
+    pub fn for_account(&self, user: Option<&User>) -> Option<&dyn AuthProvider> {
+        match user {
+            Some(user) => self.providers.iter().find(|p| p.name() == user.auth_provider).map(|p| p.as_ref()),
+            None => self.providers.first().map(|p| p.as_ref()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::insert_school;
+
+    const TEACHERS: &str = "cn=teachers,ou=groups,dc=school,dc=org";
+    const PARENTS: &str = "cn=parents,ou=groups,dc=school,dc=org";
+    const JANE_DN: &str = "uid=jdoe,ou=people,dc=school,dc=org";
+    const JANE_PASSWORD: &str = "directory pass 1";
+
+    fn fake_entry(dn: &str, password: &str, attributes: &[(&str, &[&str])]) -> FakeEntry {
+        let attributes = attributes
+            .iter()
+            .map(|(name, values)| (name.to_string(), values.iter().map(|v| v.to_string()).collect()))
+            .collect();
+        FakeEntry { entry: DirectoryEntry { dn: dn.to_string(), attributes }, password: password.to_string() }
+    }
+
+    fn jane(groups: &[&str], school: &str) -> FakeEntry {
+        fake_entry(JANE_DN, JANE_PASSWORD, &[
+            ("mail", &["Jane.Doe@school.org"]),
+            ("givenName", &["Jane"]),
+            ("sn", &["Doe"]),
+            ("o", &[school]),
+            ("memberOf", groups),
+        ])
+    }
+
+    fn provider(entries: Vec<FakeEntry>, default_school_id: Option<Uuid>) -> LdapProvider {
+        let mapping = AttributeMapping {
+            email: "mail".to_string(),
+            first_name: "givenName".to_string(),
+            last_name: "sn".to_string(),
+            role: "memberOf".to_string(),
+            roles: vec![(TEACHERS.to_string(), Role::Teacher), (PARENTS.to_string(), Role::Parent)],
+            default_role: None,
+            school: Some("o".to_string()),
+            default_school_id,
+        };
+        LdapProvider::new(Box::new(FakeDirectory::new(entries, "mail")), mapping)
+    }
+
+    async fn account_count(pool: &PgPool) -> i64 {
+        sqlx::query_scalar("SELECT count(*) FROM users").fetch_one(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn fake_directory_binds_only_with_the_right_password() {
+        let directory = FakeDirectory::new(vec![jane(&[TEACHERS], "North")], "mail");
+        assert!(directory.bind(JANE_DN, JANE_PASSWORD).await.unwrap());
+        assert!(!directory.bind(JANE_DN, "wrong").await.unwrap());
+        assert!(!directory.bind("uid=nobody,ou=people,dc=school,dc=org", JANE_PASSWORD).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn fake_directory_finds_a_single_entry_by_email() {
+        let directory = FakeDirectory::new(vec![jane(&[TEACHERS], "North")], "mail");
+        let found = directory.find_by_email("jane.doe@SCHOOL.org").await.unwrap().unwrap();
+        assert_eq!(found.dn, JANE_DN);
+        assert!(directory.find_by_email("someone@school.org").await.unwrap().is_none());
+
+        let twice = FakeDirectory::new(vec![jane(&[TEACHERS], "North"), jane(&[PARENTS], "North")], "mail");
+        assert!(twice.find_by_email("jane.doe@school.org").await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn failed_bind_creates_no_account(pool: PgPool) {
+        let ldap = provider(vec![jane(&[TEACHERS], "North")], None);
+        assert!(ldap.authenticate(&pool, "jane.doe@school.org", "wrong", None).await.unwrap().is_none());
+        assert!(ldap.authenticate(&pool, "jane.doe@school.org", "", None).await.unwrap().is_none());
+        assert!(ldap.authenticate(&pool, "nobody@school.org", JANE_PASSWORD, None).await.unwrap().is_none());
+        assert_eq!(account_count(&pool).await, 0);
+    }
+
+    #[sqlx::test]
+    async fn first_login_creates_the_account(pool: PgPool) {
+        let school_id = insert_school(&pool, "North").await;
+        let ldap = provider(vec![jane(&[TEACHERS, PARENTS], "north")], None);
+
+        let user = ldap.authenticate(&pool, "jane.doe@school.org", JANE_PASSWORD, None).await.unwrap().unwrap();
+        assert_eq!(user.email, "Jane.Doe@school.org");
+        assert_eq!(user.roles, vec![Role::Teacher, Role::Parent]);
+        assert_eq!((user.first_name.as_str(), user.last_name.as_str()), ("Jane", "Doe"));
+        assert_eq!(user.auth_provider, LDAP);
+        assert_eq!(user.school_id, Some(school_id));
+        assert_eq!(account_count(&pool).await, 1);
+    }
+
+    #[sqlx::test]
+    async fn unknown_school_falls_back_to_the_default(pool: PgPool) {
+        let default_school = insert_school(&pool, "Default").await;
+        let ldap = provider(vec![jane(&[TEACHERS], "Nowhere")], Some(default_school));
+
+        let user = ldap.authenticate(&pool, "jane.doe@school.org", JANE_PASSWORD, None).await.unwrap().unwrap();
+        assert_eq!(user.school_id, Some(default_school));
+    }
+
+    #[sqlx::test]
+    async fn later_logins_sync_roles(pool: PgPool) {
+        insert_school(&pool, "North").await;
+        let user = provider(vec![jane(&[TEACHERS], "North")], None)
+            .authenticate(&pool, "jane.doe@school.org", JANE_PASSWORD, None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Moved from the teachers to the parents group in the directory
+        let ldap = provider(vec![jane(&[PARENTS], "North")], None);
+        let synced = ldap.authenticate(&pool, "jane.doe@school.org", JANE_PASSWORD, Some(&user)).await.unwrap().unwrap();
+        assert_eq!(synced.id, user.id);
+        assert_eq!(synced.roles, vec![Role::Parent]);
+        assert_eq!(account_count(&pool).await, 1);
+
+        // Dropped from every mapped group: no longer allowed in
+        let ldap = provider(vec![jane(&["cn=alumni,ou=groups,dc=school,dc=org"], "North")], None);
+        assert!(ldap.authenticate(&pool, "jane.doe@school.org", JANE_PASSWORD, Some(&synced)).await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn directory_accounts_have_no_local_password(pool: PgPool) {
+        insert_school(&pool, "North").await;
+        let user = provider(vec![jane(&[TEACHERS], "North")], None)
+            .authenticate(&pool, "jane.doe@school.org", JANE_PASSWORD, None)
+            .await
+            .unwrap()
+            .unwrap();
+
+        for password in [JANE_PASSWORD, NO_LOCAL_PASSWORD, ""] {
+            assert!(LocalProvider.authenticate(&pool, &user.email, password, Some(&user)).await.unwrap().is_none());
+        }
+    }
+}
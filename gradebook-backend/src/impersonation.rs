@@ -0,0 +1,105 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::{generate_token, hash_token};
+
+// "View as user" for support staff. An admin gets a short-lived, read-only
+// session for the target user: it can't be refreshed, every request made with
+// it is logged with both identities, and anything but reads is refused.
+#[derive(Clone)]
+pub struct ImpersonationConfig {
+    pub ttl: Duration,
+}
+
+impl ImpersonationConfig {
+    pub fn from_env() -> Self {
+        let ttl_minutes = std::env::var("IMPERSONATION_TTL_MINUTES")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(30);
+        ImpersonationConfig { ttl: Duration::minutes(ttl_minutes) }
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LogEntry {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub impersonator_id: Uuid,
+    pub user_id: Uuid,
+    pub method: String,
+    pub path: String,
+    pub allowed: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+// Requests allowed under impersonation besides reads: ending it.
+pub fn is_allowed(method: &axum::http::Method, path: &str) -> bool {
+    *method == axum::http::Method::GET || path == "/auth/logout"
+}
+
+// Opens the impersonation session. Its refresh token is never handed out, so
+// the session ends with the access token issued for it.
+pub async fn start(pool: &PgPool, impersonator_id: Uuid, user_id: Uuid, ttl: Duration)
+    -> Result<(Uuid, DateTime<Utc>), sqlx::Error>
+{
+    let expires_at = Utc::now() + ttl;
+    let id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO sessions (user_id, refresh_token_hash, expires_at, impersonator_id)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#
+    )
+    .bind(user_id)
+    .bind(hash_token(&generate_token()))
+    .bind(expires_at)
+    .bind(impersonator_id)
+    .fetch_one(pool)
+    .await?;
+    Ok((id, expires_at))
+}
+
+pub async fn record(
+    pool: &PgPool,
+    session_id: Uuid,
+    impersonator_id: Uuid,
+    user_id: Uuid,
+    method: &str,
+    path: &str,
+    allowed: bool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO impersonation_log (session_id, impersonator_id, user_id, method, path, allowed)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#
+    )
+    .bind(session_id)
+    .bind(impersonator_id)
+    .bind(user_id)
+    .bind(method)
+    .bind(path)
+    .bind(allowed)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn recent(pool: &PgPool, user_id: Option<Uuid>, limit: i64) -> Result<Vec<LogEntry>, sqlx::Error> {
+    sqlx::query_as::<_, LogEntry>(
+        r#"
+        SELECT id, session_id, impersonator_id, user_id, method, path, allowed, created_at
+        FROM impersonation_log
+        WHERE $1::uuid IS NULL OR user_id = $1 OR impersonator_id = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#
+    )
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
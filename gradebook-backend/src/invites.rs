@@ -0,0 +1,33 @@
+use rand::Rng;
+
+use crate::auth::hash_token;
+
+// No 0/O, 1/I/L: invite codes are read off paper and typed in by hand.
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const CODE_GROUPS: usize = 3;
+const CODE_GROUP_LEN: usize = 4;
+
+pub const DEFAULT_TTL_DAYS: i64 = 14;
+
+// Human-friendly code such as "K7QM-2XRA-P9TD".
+pub fn generate_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_GROUPS)
+        .map(|_| {
+            (0..CODE_GROUP_LEN)
+                .map(|_| char::from(CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())]))
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// Case, dashes and spaces don't matter when a code is typed in.
+pub fn hash_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    hash_token(&normalized)
+}
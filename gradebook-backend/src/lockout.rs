@@ -0,0 +1,146 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// Brute-force protection for /login. Failures are tracked per account (consecutive
+// failures on `users`, which trigger a temporary lockout) and per client IP and
+// email (rows in `login_attempts`, which drive the progressive delay and IP cap).
+#[derive(Clone)]
+pub struct LockoutPolicy {
+    pub max_failures: i32,
+    pub lockout: Duration,
+    pub ip_max_failures: i64,
+    pub window: Duration,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+impl LockoutPolicy {
+    pub fn from_env() -> Self {
+        LockoutPolicy {
+            max_failures: env_or("LOGIN_MAX_FAILURES", 5),
+            lockout: Duration::minutes(env_or("LOGIN_LOCKOUT_MINUTES", 15)),
+            ip_max_failures: env_or("LOGIN_IP_MAX_FAILURES", 50),
+            window: Duration::minutes(env_or("LOGIN_WINDOW_MINUTES", 15)),
+            base_delay_ms: env_or("LOGIN_BASE_DELAY_MS", 250),
+            max_delay_ms: env_or("LOGIN_MAX_DELAY_MS", 8000),
+        }
+    }
+
+    // Doubles with every recent failure: 0, base, 2*base, 4*base, ... capped at max.
+    pub fn delay_for(&self, recent_failures: i64) -> std::time::Duration {
+        if recent_failures <= 0 {
+            return std::time::Duration::ZERO;
+        }
+        let exponent = (recent_failures - 1).min(16) as u32;
+        let delay = self.base_delay_ms.saturating_mul(1u64 << exponent).min(self.max_delay_ms);
+        std::time::Duration::from_millis(delay)
+    }
+}
+
+pub async fn recent_ip_failures(pool: &PgPool, ip: &str, policy: &LockoutPolicy) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM login_attempts WHERE ip_address = $1 AND NOT succeeded AND attempted_at > $2"
+    )
+    .bind(ip)
+    .bind(Utc::now() - policy.window)
+    .fetch_one(pool)
+    .await
+}
+
+// Attempts are kept per email as it would match an account, so changing the case
+// of the typed address doesn't start a fresh count
+fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+pub async fn recent_email_failures(pool: &PgPool, email: &str, policy: &LockoutPolicy) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM login_attempts WHERE email = $1 AND NOT succeeded AND attempted_at > $2"
+    )
+    .bind(normalize_email(email))
+    .bind(Utc::now() - policy.window)
+    .fetch_one(pool)
+    .await
+}
+
+async fn record_attempt(pool: &PgPool, email: &str, ip: &str, succeeded: bool) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO login_attempts (email, ip_address, succeeded) VALUES ($1, $2, $3)")
+        .bind(normalize_email(email))
+        .bind(ip)
+        .bind(succeeded)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// Records a failed attempt and, for a known account, bumps its failure counter.
+// Reaching the limit locks the account and starts a fresh count for after the
+// lockout. Returns the lockout expiry if the account is locked.
+pub async fn record_failure(pool: &PgPool, user_id: Option<Uuid>, email: &str, ip: &str, policy: &LockoutPolicy)
+    -> Result<Option<DateTime<Utc>>, sqlx::Error>
+{
+    record_attempt(pool, email, ip, false).await?;
+
+    let Some(user_id) = user_id else {
+        return Ok(None);
+    };
+
+    let locked_until: Option<DateTime<Utc>> = sqlx::query_scalar(
+        r#"
+        UPDATE users
+        SET failed_login_attempts = CASE
+                WHEN failed_login_attempts + 1 >= $2 THEN 0
+                ELSE failed_login_attempts + 1
+            END,
+            locked_until = CASE
+                WHEN failed_login_attempts + 1 >= $2 THEN $3
+                ELSE locked_until
+            END
+        WHERE id = $1
+        RETURNING locked_until
+        "#
+    )
+    .bind(user_id)
+    .bind(policy.max_failures)
+    .bind(Utc::now() + policy.lockout)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(locked_until.filter(|until| *until > Utc::now()))
+}
+
+pub async fn record_success(pool: &PgPool, user_id: Uuid, email: &str, ip: &str) -> Result<(), sqlx::Error> {
+    record_attempt(pool, email, ip, true).await?;
+    unlock(pool, user_id).await?;
+    Ok(())
+}
+
+pub async fn unlock(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE users SET failed_login_attempts = 0, locked_until = NULL WHERE id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn email_case_shares_one_failure_count(pool: PgPool) {
+        let policy = LockoutPolicy::from_env();
+        record_failure(&pool, None, "Alice@Example.com", "192.0.2.1", &policy).await.unwrap();
+        record_failure(&pool, None, " ALICE@example.COM", "192.0.2.2", &policy).await.unwrap();
+
+        for email in ["alice@example.com", "aLiCe@EXAMPLE.com "] {
+            assert_eq!(recent_email_failures(&pool, email, &policy).await.unwrap(), 2, "{}", email);
+        }
+        assert_eq!(recent_email_failures(&pool, "bob@example.com", &policy).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+    #[error("failed to write to outbox: {0}")]
+    Outbox(#[from] std::io::Error),
+}
+
+// Outgoing mail. Handlers only see this trait, so delivery can be swapped per
+// deployment (and checked offline via the outbox).
+#[axum::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), MailError>;
+}
+
+// Writes every message as an .eml file into a directory instead of delivering it.
+pub struct OutboxMailer {
+    dir: PathBuf,
+    from: String,
+}
+
+impl OutboxMailer {
+    pub fn new(dir: impl Into<PathBuf>, from: impl Into<String>) -> Self {
+        OutboxMailer { dir: dir.into(), from: from.into() }
+    }
+}
+
+#[axum::async_trait]
+impl Mailer for OutboxMailer {
+    async fn send(&self, email: Email) -> Result<(), MailError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let now = Utc::now();
+        let path = self.dir.join(format!("{}-{}.eml", now.format("%Y%m%dT%H%M%S"), Uuid::new_v4()));
+        let message = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+            self.from,
+            email.to,
+            email.subject,
+            now.to_rfc2822(),
+            email.body,
+        );
+        tokio::fs::write(&path, message).await?;
+        tracing::info!(to = %email.to, path = %path.display(), "mail written to outbox");
+        Ok(())
+    }
+}
+
+pub fn from_env() -> Arc<dyn Mailer> {
+    let from = std::env::var("MAIL_FROM").unwrap_or_else(|_| "Gradebook <no-reply@gradebook.local>".to_string());
+    match std::env::var("MAILER").unwrap_or_else(|_| "outbox".to_string()).as_str() {
+        "outbox" => {
+            let dir = std::env::var("MAILER_OUTBOX_DIR").unwrap_or_else(|_| "outbox".to_string());
+            Arc::new(OutboxMailer::new(dir, from))
+        }
+        other => panic!("Unknown MAILER backend: {}", other),
+    }
+}
@@ -1,9 +1,34 @@
+mod api_keys;
+mod auth;
+mod auth_provider;
 mod db;
+mod impersonation;
+mod invites;
+mod lockout;
+mod mailer;
 mod models;
+mod password;
+mod policy;
+mod password_policy;
+mod password_reset;
+mod sessions;
+mod totp;
+#[cfg(test)]
+mod test_support;
+use auth::AuthConfig;
+use auth_provider::AuthProviders;
+use impersonation::ImpersonationConfig;
+use lockout::LockoutPolicy;
+use mailer::Mailer;
+use password_policy::{PasswordPolicy, PolicyRejection};
+use policy::{Action, Resource, StudentFacts, GradeFacts, AbsenceFacts, ClassFacts, TeacherFacts};
+use password_reset::ResetConfig;
+use totp::{MfaPolicy, TotpEnrollment, TotpStatus};
 use axum::extract::FromRef;
 use axum::{
-    extract::{State, Path, FromRequestParts},
-    http::{StatusCode, request::Parts},
+    extract::{State, Path, Query, FromRequestParts, ConnectInfo, MatchedPath},
+    http::{StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
     routing::{get, post, put, delete},
     Json, Router,
 };
@@ -11,63 +36,562 @@ use models::parent_student::{ParentStudent, LinkParentStudent};
 use models::grade::{Grade, NewGrade};
 use models::absence::{Absence, NewAbsence};
 use models::user::{User, Role, NewUser};
+use models::invite::{Invite, NewInvite, CreatedInvite, Registration};
+use models::api_key::{ApiKey, NewApiKey, CreatedApiKey};
+use models::curriculum::{CurriculumEntry, NewCurriculumEntry, CopyCurriculum};
 use models::student::{Student, NewStudent};
+use models::school::{School, NewSchool};
+use models::class::{Class, NewClass};
+use models::subject::{Subject, NewSubject, MergeSubjects, MergedSubject};
+use models::teacher::{TeacherProfile, UpdateTeacherProfile, TeacherQualifications};
+use models::term::{AcademicYear, NewAcademicYear, Term, NewTerm};
 use serde::{Serialize, Deserialize};
 use sqlx::PgPool;
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use uuid::Uuid;
 use tower_http::cors::{CorsLayer, Any};
 
-#[derive(Serialize)]
+#[derive(Serialize, sqlx::FromRow)]
 struct StudentAvgGrade {
     student_id: uuid::Uuid,
     avg_grade: Option<f64>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, sqlx::FromRow)]
 struct StudentAbsenceCount {
     student_id: uuid::Uuid,
     absence_count: i64,
 }
 
-// === User extraction (Replace with session/JWT auth in production) ===
-// Looks for header "x-user-id" and loads user from DB.
+// === Shared application state ===
+#[derive(Clone)]
+struct AppState {
+    pool: PgPool,
+    auth: AuthConfig,
+    providers: AuthProviders,
+    lockout: LockoutPolicy,
+    mfa: MfaPolicy,
+    reset: ResetConfig,
+    impersonation: ImpersonationConfig,
+    passwords: PasswordPolicy,
+    mailer: Arc<dyn Mailer>,
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for AuthConfig {
+    fn from_ref(state: &AppState) -> Self {
+        state.auth.clone()
+    }
+}
+
+impl FromRef<AppState> for AuthProviders {
+    fn from_ref(state: &AppState) -> Self {
+        state.providers.clone()
+    }
+}
+
+impl FromRef<AppState> for LockoutPolicy {
+    fn from_ref(state: &AppState) -> Self {
+        state.lockout.clone()
+    }
+}
+
+impl FromRef<AppState> for MfaPolicy {
+    fn from_ref(state: &AppState) -> Self {
+        state.mfa.clone()
+    }
+}
+
+impl FromRef<AppState> for ResetConfig {
+    fn from_ref(state: &AppState) -> Self {
+        state.reset.clone()
+    }
+}
+
+impl FromRef<AppState> for ImpersonationConfig {
+    fn from_ref(state: &AppState) -> Self {
+        state.impersonation.clone()
+    }
+}
+
+impl FromRef<AppState> for PasswordPolicy {
+    fn from_ref(state: &AppState) -> Self {
+        state.passwords.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn Mailer> {
+    fn from_ref(state: &AppState) -> Self {
+        state.mailer.clone()
+    }
+}
+
+// === User extraction ===
+// Expects "Authorization: Bearer <access token>", verifies signature, expiry and
+// claims, then loads the user together with the (still active) session the
+// token was issued for. Impersonation sessions additionally carry the admin
+// behind them; their requests are logged and limited to reads.
+struct AuthSession {
+    user: User,
+    session_id: Uuid,
+    impersonator_id: Option<Uuid>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SessionUser {
+    #[sqlx(flatten)]
+    user: User,
+    impersonator_id: Option<Uuid>,
+}
+
 #[axum::async_trait]
-impl<S> FromRequestParts<S> for User
+impl<S> FromRequestParts<S> for AuthSession
 where
-    PgPool: axum::extract::FromRef<S>,
+    PgPool: FromRef<S>,
+    AuthConfig: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = (StatusCode, String);
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        let user_id = parts
-            .headers
-            .get("x-user-id")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| Uuid::parse_str(s).ok())
-            .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Missing or invalid x-user-id header".to_string()))?;
+        let token = bearer_from_parts(parts)?;
+        if api_keys::is_api_key(token) {
+            return Err((StatusCode::FORBIDDEN, "This endpoint requires a user login, not an API key".to_string()));
+        }
+
+        let claims = AuthConfig::from_ref(state)
+            .verify_access_token(token)
+            .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid or expired access token".to_string()))?;
 
         let pool = PgPool::from_ref(state);
-        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
-            .bind(user_id)
-            .fetch_one(&pool)
-            .await
-            .map_err(|_| (StatusCode::UNAUTHORIZED, "User not found".to_string()))?;
+        // An impersonation session dies with its admin's role
+        let SessionUser { user, impersonator_id } = sqlx::query_as::<_, SessionUser>(
+            r#"
+            SELECT u.*, s.impersonator_id FROM users u
+            JOIN sessions s ON s.user_id = u.id
+            LEFT JOIN users a ON a.id = s.impersonator_id
+            WHERE u.id = $1 AND s.id = $2 AND s.revoked_at IS NULL AND s.expires_at > now()
+              AND (s.impersonator_id IS NULL OR 'admin' = ANY(a.roles))
+            "#
+        )
+        .bind(claims.sub)
+        .bind(claims.sid)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Session expired or revoked".to_string()))?;
+
+        if user.roles != claims.roles || impersonator_id != claims.act {
+            return Err((StatusCode::UNAUTHORIZED, "Access token is no longer valid".to_string()));
+        }
+
+        if let Some(impersonator_id) = impersonator_id {
+            let path = parts.uri.path();
+            let allowed = impersonation::is_allowed(&parts.method, path);
+            impersonation::record(&pool, claims.sid, impersonator_id, user.id, parts.method.as_str(), path, allowed)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            tracing::info!(%impersonator_id, user_id = %user.id, method = %parts.method, path, allowed, "impersonated request");
+            if !allowed {
+                return Err((StatusCode::FORBIDDEN, "Not allowed while viewing as another user".to_string()));
+            }
+        }
+
+        Ok(AuthSession { user, session_id: claims.sid, impersonator_id })
+    }
+}
+
+fn bearer_from_parts(parts: &Parts) -> Result<&str, (StatusCode, String)> {
+    parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(auth::bearer_token)
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Missing or invalid Authorization header".to_string()))
+}
+
+#[derive(sqlx::FromRow)]
+struct ApiKeyGrant {
+    id: Uuid,
+    scopes: Vec<String>,
+    school_id: Option<Uuid>,
+    created_by: Uuid,
+}
+
+// "Authorization: Bearer gbk_..." from a machine integration. The key acts as the
+// admin who minted it, or as a director of its school if it is limited to one,
+// but only for routes its scopes cover; everything else (own profile, user
+// management, sessions, TOTP, key management) needs a human login.
+async fn authenticate_api_key(parts: &Parts, pool: &PgPool, key: &str) -> Result<User, (StatusCode, String)> {
+    let grant = sqlx::query_as::<_, ApiKeyGrant>(
+        r#"
+        SELECT k.id, k.scopes, k.school_id, k.created_by FROM api_keys k
+        JOIN users u ON u.id = k.created_by
+        WHERE k.key_hash = $1 AND k.revoked_at IS NULL AND k.expires_at > now() AND 'admin' = ANY(u.roles)
+        "#
+    )
+    .bind(api_keys::hash_key(key))
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Invalid, expired or revoked API key".to_string()))?;
+
+    let route = parts.extensions.get::<MatchedPath>().map(|path| path.as_str()).unwrap_or_default();
+    let allowed = api_keys::required_scope(&parts.method, route).is_some_and(|scope| grant.scopes.contains(&scope));
+    if !allowed {
+        return Err((StatusCode::FORBIDDEN, "API key is not allowed to perform this operation".to_string()));
+    }
+
+    sqlx::query("UPDATE api_keys SET last_used_at = now() WHERE id = $1")
+        .bind(grant.id)
+        .execute(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(grant.created_by)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if let Some(school_id) = grant.school_id {
+        user.roles = vec![Role::Director];
+        user.school_id = Some(school_id);
+    }
+    Ok(user)
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for User
+where
+    PgPool: FromRef<S>,
+    AuthConfig: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let token = bearer_from_parts(parts)?;
+        if api_keys::is_api_key(token) {
+            let key = token.to_string();
+            return authenticate_api_key(parts, &PgPool::from_ref(state), &key).await;
+        }
+        AuthSession::from_request_parts(parts, state).await.map(|session| session.user)
+    }
+}
+
+// === Authorization ===
+// The rules live in `policy`; these helpers load the facts they need about a record.
+fn authorize(user: &User, action: Action, resource: &Resource) -> Result<(), (StatusCode, String)> {
+    if policy::can(user, action, resource) {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, format!("Insufficient permissions for roles {:?}", user.roles)))
+    }
+}
+
+// Tenancy check for records that aren't a `Resource` of their own, such as the
+// school a new student is placed in. Same rule as `policy::can` applies to students.
+fn authorize_school(user: &User, school_id: Option<Uuid>) -> Result<(), (StatusCode, String)> {
+    if policy::in_school(user, school_id) {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "This record belongs to another school".to_string()))
+    }
+}
+
+// SQL condition limiting the school id in `column` to the acting user's school;
+// "TRUE" for admins and "FALSE" for anyone else without a school. The list
+// counterpart of `policy::in_school`, used by every listing so that tenancy isn't
+// left to each query. Taken from `user` rather than the database, so an API key
+// limited to a school is held to that school.
+fn tenant_condition(user: &User, column: &str) -> String {
+    if user.has_role(Role::Admin) {
+        return "TRUE".to_string();
+    }
+    match user.school_id {
+        Some(school_id) => format!("{} = '{}'", column, school_id),
+        None => "FALSE".to_string(),
+    }
+}
+
+// School of an existing account
+async fn school_of_user(pool: &PgPool, user_id: Uuid) -> Result<Option<Uuid>, (StatusCode, String)> {
+    sqlx::query_scalar::<_, Option<Uuid>>("SELECT school_id FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "User not found".to_string()))
+}
+
+// SQL condition: the teacher whose id is bound as `teacher` teaches the class in
+// `class_id` by its curriculum (any term, any subject) or is its homeroom teacher.
+fn teaches_class(teacher: &str, class_id: &str) -> String {
+    format!(
+        "(EXISTS (SELECT 1 FROM curriculum_entries ce JOIN curriculum_teachers ct ON ct.entry_id = ce.id \
+                  WHERE ct.teacher_id = {teacher} AND ce.class_id = {class_id}) \
+         OR EXISTS (SELECT 1 FROM classes hc WHERE hc.id = {class_id} AND hc.homeroom_teacher_id = {teacher}))"
+    )
+}
+
+// SQL expression: the term of the school in `school_id` that the day in `day`
+// falls in, or NULL outside of terms.
+fn term_for(school_id: &str, day: &str) -> String {
+    format!(
+        "(SELECT t.id FROM terms t JOIN academic_years y ON y.id = t.academic_year_id \
+         WHERE y.school_id = {school_id} AND {day} BETWEEN t.starts_on AND t.ends_on LIMIT 1)"
+    )
+}
+
+async fn student_facts(pool: &PgPool, user: &User, student_id: Uuid) -> Result<StudentFacts, (StatusCode, String)> {
+    let sql = format!(
+        r#"
+        SELECT s.user_id, s.school_id,
+               EXISTS (SELECT 1 FROM parent_students ps WHERE ps.student_id = s.id AND ps.parent_id = $2),
+               {}
+        FROM students s WHERE s.id = $1
+        "#,
+        teaches_class("$2", "s.class_id")
+    );
+    let (user_id, school_id, is_child_of_actor, taught_by_actor) = sqlx::query_as::<_, (Uuid, Option<Uuid>, bool, bool)>(&sql)
+    .bind(student_id)
+    .bind(user.id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "Student not found".to_string()))?;
+    Ok(StudentFacts { user_id, school_id, is_child_of_actor, taught_by_actor })
+}
+
+async fn class_facts(pool: &PgPool, user: &User, class_id: Uuid) -> Result<ClassFacts, (StatusCode, String)> {
+    let sql = format!("SELECT c.school_id, {} FROM classes c WHERE c.id = $1", teaches_class("$2", "c.id"));
+    let (school_id, taught_by_actor) = sqlx::query_as::<_, (Uuid, bool)>(&sql)
+        .bind(class_id)
+        .bind(user.id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Class not found".to_string()))?;
+    Ok(ClassFacts { school_id, taught_by_actor })
+}
+
+async fn teacher_facts(pool: &PgPool, teacher_id: Uuid) -> Result<TeacherFacts, (StatusCode, String)> {
+    let school_id = sqlx::query_scalar::<_, Option<Uuid>>("SELECT school_id FROM users WHERE id = $1 AND 'teacher' = ANY(roles)")
+        .bind(teacher_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Teacher not found".to_string()))?;
+    Ok(TeacherFacts { user_id: teacher_id, school_id })
+}
+
+// SQL condition on `students s` selecting the students `user` may see through any
+// of their roles, with the user's id bound as $1; None means all of them. The
+// list counterpart of the per-student rule in `policy::can`.
+fn student_scope(user: &User) -> Option<String> {
+    let teacher_condition = teaches_class("$1", "s.class_id");
+    let mut conditions = Vec::new();
+    for role in &user.roles {
+        conditions.push(match role {
+            Role::Admin => return None,
+            Role::Director => "TRUE",
+            Role::Teacher => teacher_condition.as_str(),
+            Role::Parent => "s.id IN (SELECT student_id FROM parent_students WHERE parent_id = $1)",
+            Role::Student => "s.user_id = $1",
+        });
+    }
+    Some(format!("{} AND ({})", tenant_condition(user, "s.school_id"), conditions.join(" OR ")))
+}
+
+// `?school_id=` accepted by the list and statistics endpoints
+#[derive(Deserialize)]
+struct SchoolFilter { school_id: Option<Uuid> }
+
+// Filter for grades and absences and the statistics over them
+#[derive(Deserialize)]
+struct RecordFilter { school_id: Option<Uuid>, term_id: Option<Uuid>, academic_year_id: Option<Uuid> }
+
+// SQL condition on a record's `term_id` for `RecordFilter`, bound as $3 and $4
+const TERM_FILTER: &str =
+    "($3::uuid IS NULL OR term_id = $3) AND ($4::uuid IS NULL OR term_id IN (SELECT id FROM terms WHERE academic_year_id = $4))";
+
+// Whether the account exists and holds the teacher role (among others).
+async fn is_teacher(pool: &PgPool, user_id: Uuid) -> Result<bool, (StatusCode, String)> {
+    sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND 'teacher' = ANY(roles))")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+// The term of the student's school that the day falls in, if any.
+async fn term_on(pool: &PgPool, student_id: Uuid, day: chrono::NaiveDate) -> Result<Option<Uuid>, (StatusCode, String)> {
+    let sql = format!("SELECT {} FROM students s WHERE s.id = $1", term_for("s.school_id", "$2::date"));
+    sqlx::query_scalar::<_, Option<Uuid>>(&sql)
+        .bind(student_id)
+        .bind(day)
+        .fetch_optional(pool)
+        .await
+        .map(Option::flatten)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+// Facts for a grade in the subject and term (None: outside of terms) for the
+// given student, existing or about to be created.
+async fn grade_facts_for(pool: &PgPool, user: &User, student_id: Uuid, subject_id: Uuid, term_id: Option<Uuid>)
+    -> Result<GradeFacts, (StatusCode, String)>
+{
+    let student = student_facts(pool, user, student_id).await?;
+    let actor_teaches_subject = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM curriculum_entries ce
+            JOIN curriculum_teachers ct ON ct.entry_id = ce.id
+            JOIN students s ON s.class_id = ce.class_id
+            WHERE s.id = $1 AND ct.teacher_id = $2 AND ce.subject_id = $3 AND ($4::uuid IS NULL OR ce.term_id = $4)
+        )
+        "#
+    )
+    .bind(student_id)
+    .bind(user.id)
+    .bind(subject_id)
+    .bind(term_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(GradeFacts { student, actor_teaches_subject })
+}
+
+// Facts for an existing grade, with its student and term
+async fn grade_facts(pool: &PgPool, user: &User, grade_id: Uuid) -> Result<(GradeFacts, Uuid, Option<Uuid>), (StatusCode, String)> {
+    let (student_id, subject_id, term_id) = sqlx::query_as::<_, (Uuid, Uuid, Option<Uuid>)>(
+        "SELECT student_id, subject_id, term_id FROM grades WHERE id = $1"
+    )
+    .bind(grade_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "Grade not found".to_string()))?;
+    Ok((grade_facts_for(pool, user, student_id, subject_id, term_id).await?, student_id, term_id))
+}
 
-        Ok(user)
+// New grades and curriculum entries need a subject from the catalog that is still offered.
+async fn check_subject_active(pool: &PgPool, subject_id: Uuid) -> Result<(), (StatusCode, String)> {
+    let active = sqlx::query_scalar::<_, bool>("SELECT active FROM subjects WHERE id = $1")
+        .bind(subject_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "subject_id must refer to a subject".to_string()))?;
+    if active {
+        Ok(())
+    } else {
+        Err((StatusCode::BAD_REQUEST, "This subject is no longer active".to_string()))
     }
 }
 
-// === Role guard helper ===
-fn require_role(user: &User, allowed: &[Role]) -> Result<(), (StatusCode, String)> {
-    if allowed.contains(&user.role) {
+// Grades can only be given by teachers qualified for the subject (`/teachers/:id/subjects`).
+async fn check_teacher_qualified(pool: &PgPool, teacher_id: Uuid, subject_id: Uuid) -> Result<(), (StatusCode, String)> {
+    let qualified = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM teacher_qualifications WHERE teacher_id = $1 AND subject_id = $2)"
+    )
+    .bind(teacher_id)
+    .bind(subject_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if qualified {
         Ok(())
     } else {
-        Err((StatusCode::FORBIDDEN, format!("Insufficient permissions for role {:?}", user.role)))
+        Err((StatusCode::BAD_REQUEST, "The teacher is not qualified to teach this subject".to_string()))
+    }
+}
+
+// Facts for an absence of the given student in the term (None: outside of
+// terms), existing or about to be recorded.
+async fn absence_facts_for(pool: &PgPool, user: &User, student_id: Uuid, term_id: Option<Uuid>)
+    -> Result<AbsenceFacts, (StatusCode, String)>
+{
+    let student = student_facts(pool, user, student_id).await?;
+    let actor_teaches_class = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM curriculum_entries ce
+            JOIN curriculum_teachers ct ON ct.entry_id = ce.id
+            JOIN students s ON s.class_id = ce.class_id
+            WHERE s.id = $1 AND ct.teacher_id = $2 AND ($3::uuid IS NULL OR ce.term_id = $3)
+        ) OR EXISTS (
+            SELECT 1 FROM students s JOIN classes c ON c.id = s.class_id WHERE s.id = $1 AND c.homeroom_teacher_id = $2
+        )
+        "#
+    )
+    .bind(student_id)
+    .bind(user.id)
+    .bind(term_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(AbsenceFacts { student, actor_teaches_class })
+}
+
+// Facts for an existing absence, with its student
+async fn absence_facts(pool: &PgPool, user: &User, absence_id: Uuid) -> Result<(AbsenceFacts, Uuid), (StatusCode, String)> {
+    let (student_id, term_id) = sqlx::query_as::<_, (Uuid, Option<Uuid>)>("SELECT student_id, term_id FROM absences WHERE id = $1")
+        .bind(absence_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Absence not found".to_string()))?;
+    Ok((absence_facts_for(pool, user, student_id, term_id).await?, student_id))
+}
+
+// === Password policy helper ===
+// Error type of handlers that set a password: the usual status + message, or the
+// list of password policy violations (422 with a JSON body).
+enum PasswordChangeError {
+    Status(StatusCode, String),
+    Policy(PolicyRejection),
+}
+
+impl From<(StatusCode, String)> for PasswordChangeError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        PasswordChangeError::Status(status, message)
     }
 }
 
+impl From<PolicyRejection> for PasswordChangeError {
+    fn from(rejection: PolicyRejection) -> Self {
+        PasswordChangeError::Policy(rejection)
+    }
+}
+
+impl IntoResponse for PasswordChangeError {
+    fn into_response(self) -> Response {
+        match self {
+            PasswordChangeError::Status(status, message) => (status, message).into_response(),
+            PasswordChangeError::Policy(rejection) => rejection.into_response(),
+        }
+    }
+}
+
+// `identity`: the email and names the account is getting, if any
+async fn enforce_password_policy(policy: &PasswordPolicy, pool: &PgPool, user_id: Option<Uuid>, new_password: &str, identity: &[&str])
+    -> Result<(), PasswordChangeError>
+{
+    policy
+        .check(pool, user_id, new_password, identity)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))??;
+    Ok(())
+}
+
 // === Health check ===
 async fn health_check(State(pool): State<PgPool>) -> &'static str {
     if sqlx::query("SELECT 1").execute(&pool).await.is_ok() {
@@ -81,144 +605,2075 @@ async fn health_check(State(pool): State<PgPool>) -> &'static str {
 #[derive(Deserialize)]
 struct LoginRequest { email: String, password: String }
 #[derive(Serialize)]
-struct LoginResponse { user_id: Uuid, role: Role }
+struct LoginResponse {
+    user_id: Uuid,
+    // Most privileged of `roles`, for clients that only know a single role
+    role: Role,
+    roles: Vec<Role>,
+    access_token: String,
+    token_type: &'static str,
+    expires_in: i64,
+    refresh_token: String,
+    refresh_expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Returned by /login instead of tokens when the password was right but a TOTP
+// code is still needed. `enrollment_required` means the user must set up TOTP
+// first (via /login/totp/enroll) because policy makes it mandatory for the role.
+#[derive(Serialize)]
+struct MfaChallenge {
+    user_id: Uuid,
+    mfa_required: bool,
+    enrollment_required: bool,
+    mfa_token: String,
+    expires_in: i64,
+}
 
-async fn login(State(pool): State<PgPool>, Json(input): Json<LoginRequest>)
-    -> Result<Json<LoginResponse>, (StatusCode, String)>
+#[derive(Serialize)]
+#[serde(untagged)]
+enum LoginOutcome {
+    Authenticated(LoginResponse),
+    MfaRequired(MfaChallenge),
+}
+
+fn token_response(auth: &AuthConfig, user: User, session: sessions::IssuedSession)
+    -> Result<LoginResponse, (StatusCode, String)>
 {
-    let user = sqlx::query_as::<_, User>(
-        r#"
-        SELECT * FROM users WHERE email = $1 AND hashed_password = crypt($2, hashed_password)
-        "#
-    )
-    .bind(&input.email)
-    .bind(&input.password)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| {
-        println!("Login error: {:?}", e);
-        (StatusCode::UNAUTHORIZED, "Invalid email or password".to_string())
-    })?;
+    let access_token = auth
+        .issue_access_token(&user, session.id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(LoginResponse {
+        user_id: user.id,
+        role: user.primary_role(),
+        roles: user.roles,
+        access_token,
+        token_type: "Bearer",
+        expires_in: auth.access_ttl.num_seconds(),
+        refresh_token: session.refresh_token,
+        refresh_expires_at: session.expires_at,
+    })
+}
+
+async fn open_session(pool: &PgPool, auth: &AuthConfig, user: User)
+    -> Result<LoginResponse, (StatusCode, String)>
+{
+    let session = sessions::create(pool, user.id, auth.refresh_ttl)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    token_response(auth, user, session)
+}
+
+fn ensure_not_locked(user: &User) -> Result<(), (StatusCode, String)> {
+    match user.locked_until.filter(|until| *until > chrono::Utc::now()) {
+        Some(until) => Err((StatusCode::TOO_MANY_REQUESTS, format!("Account is temporarily locked until {}", until))),
+        None => Ok(()),
+    }
+}
+
+async fn login(
+    State(pool): State<PgPool>,
+    State(auth): State<AuthConfig>,
+    State(providers): State<AuthProviders>,
+    State(policy): State<LockoutPolicy>,
+    State(mfa): State<MfaPolicy>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(input): Json<LoginRequest>,
+) -> Result<Json<LoginOutcome>, (StatusCode, String)>
+{
+    let ip = addr.ip().to_string();
+
+    let ip_failures = lockout::recent_ip_failures(&pool, &ip, &policy)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if ip_failures >= policy.ip_max_failures {
+        tracing::warn!(%ip, "login rejected: too many failed attempts from address");
+        return Err((StatusCode::TOO_MANY_REQUESTS, "Too many failed login attempts, try again later".to_string()));
+    }
+
+    // Emails match regardless of case, like in the directory; an exact match wins
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE lower(email) = lower($1) ORDER BY email = $1 DESC LIMIT 1")
+        .bind(&input.email)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if let Some(user) = &user {
+        ensure_not_locked(user).inspect_err(|_| {
+            tracing::warn!(email = %input.email, %ip, "login rejected: account locked");
+        })?;
+    }
+
+    // Progressive delay before every check, growing with recent failures for this email
+    let email_failures = lockout::recent_email_failures(&pool, &input.email, &policy)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    tokio::time::sleep(policy.delay_for(email_failures)).await;
+
+    // The account's own provider checks the password (the primary one for unknown
+    // emails, which may create the account from a directory entry)
+    let authenticated = match providers.for_account(user.as_ref()) {
+        Some(provider) => provider.authenticate(&pool, &input.email, &input.password, user.as_ref()).await,
+        None => {
+            tracing::warn!(email = %input.email, "login rejected: account's auth provider is not configured");
+            password::verify_nothing(&input.password).await.map(|_| None).map_err(Into::into)
+        }
+    }
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let user = match authenticated {
+        Some(user) => user,
+        None => {
+            let locked_until = lockout::record_failure(&pool, user.map(|u| u.id), &input.email, &ip, &policy)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            tracing::warn!(email = %input.email, %ip, "failed login attempt");
+            if let Some(until) = locked_until {
+                tracing::warn!(email = %input.email, %until, "account locked after repeated login failures");
+            }
+            return Err((StatusCode::UNAUTHORIZED, "Invalid email or password".to_string()));
+        }
+    };
+
+    lockout::record_success(&pool, user.id, &input.email, &ip)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let totp_enabled = totp::status(&pool, user.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        == TotpStatus::Enabled;
+
+    if totp_enabled || mfa.is_required_for(&user) {
+        let mfa_token = auth
+            .issue_mfa_token(user.id)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        return Ok(Json(LoginOutcome::MfaRequired(MfaChallenge {
+            user_id: user.id,
+            mfa_required: true,
+            enrollment_required: !totp_enabled,
+            mfa_token,
+            expires_in: auth.mfa_token_ttl().num_seconds(),
+        })));
+    }
+
+    Ok(Json(LoginOutcome::Authenticated(open_session(&pool, &auth, user).await?)))
+}
+
+// === Two-factor authentication (TOTP) ===
+// Second login step: trade the challenge token plus a TOTP or recovery code for a session.
+#[derive(Deserialize)]
+struct TotpLoginRequest {
+    mfa_token: String,
+    code: Option<String>,
+    recovery_code: Option<String>,
+}
+
+async fn user_for_mfa_token(pool: &PgPool, auth: &AuthConfig, mfa_token: &str)
+    -> Result<User, (StatusCode, String)>
+{
+    let claims = auth
+        .verify_mfa_token(mfa_token)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid or expired MFA token".to_string()))?;
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(claims.sub)
+        .fetch_one(pool)
+        .await
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "User not found".to_string()))?;
+    ensure_not_locked(&user)?;
+    Ok(user)
+}
+
+async fn login_totp(
+    State(pool): State<PgPool>,
+    State(auth): State<AuthConfig>,
+    State(policy): State<LockoutPolicy>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(input): Json<TotpLoginRequest>,
+) -> Result<Json<LoginResponse>, (StatusCode, String)>
+{
+    let user = user_for_mfa_token(&pool, &auth, &input.mfa_token).await?;
+
+    let verified = match (&input.code, &input.recovery_code) {
+        (Some(code), _) => totp::verify_code(&pool, &user, code).await,
+        (None, Some(recovery_code)) => totp::use_recovery_code(&pool, user.id, recovery_code).await,
+        (None, None) => return Err((StatusCode::BAD_REQUEST, "Provide either code or recovery_code".to_string())),
+    }
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if !verified {
+        let ip = addr.ip().to_string();
+        lockout::record_failure(&pool, Some(user.id), &user.email, &ip, &policy)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        tracing::warn!(email = %user.email, %ip, "failed two-factor code");
+        return Err((StatusCode::UNAUTHORIZED, "Invalid two-factor code".to_string()));
+    }
+
+    Ok(Json(open_session(&pool, &auth, user).await?))
+}
+
+// Enrollment during a login challenge, for users who must have TOTP but have none yet
+#[derive(Deserialize)]
+struct MfaTokenRequest { mfa_token: String }
+
+async fn login_totp_enroll(State(pool): State<PgPool>, State(auth): State<AuthConfig>, Json(input): Json<MfaTokenRequest>)
+    -> Result<Json<TotpEnrollment>, (StatusCode, String)>
+{
+    let user = user_for_mfa_token(&pool, &auth, &input.mfa_token).await?;
+    start_totp_enrollment(&pool, &user).await
+}
+
+async fn start_totp_enrollment(pool: &PgPool, user: &User) -> Result<Json<TotpEnrollment>, (StatusCode, String)> {
+    totp::start_enrollment(pool, user)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| (StatusCode::CONFLICT, "Two-factor authentication is already enabled".to_string()))
+}
+
+// Self-service: any logged-in user may opt into TOTP
+async fn setup_totp(State(pool): State<PgPool>, user: User)
+    -> Result<Json<TotpEnrollment>, (StatusCode, String)>
+{
+    start_totp_enrollment(&pool, &user).await
+}
+
+#[derive(Deserialize)]
+struct TotpCodeRequest { code: String }
+
+async fn confirm_totp(State(pool): State<PgPool>, user: User, Json(input): Json<TotpCodeRequest>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    let verified = totp::verify_code(&pool, &user, &input.code)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if verified {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::BAD_REQUEST, "Invalid two-factor code".to_string()))
+    }
+}
+
+async fn disable_totp(State(pool): State<PgPool>, State(mfa): State<MfaPolicy>, user: User, Json(input): Json<TotpCodeRequest>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    if mfa.is_required_for(&user) {
+        return Err((StatusCode::FORBIDDEN, "Two-factor authentication is mandatory for your role".to_string()));
+    }
+    let verified = totp::verify_code(&pool, &user, &input.code)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !verified {
+        return Err((StatusCode::BAD_REQUEST, "Invalid two-factor code".to_string()));
+    }
+    totp::disable(&pool, user.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Admin: reset TOTP for a user who lost their device
+async fn reset_user_totp(State(pool): State<PgPool>, user: User, Path(user_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    authorize(&user, Action::Update, &Resource::Users)?;
+    totp::disable(&pool, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// === Session refresh / logout ===
+#[derive(Deserialize)]
+struct RefreshRequest { refresh_token: String }
+
+async fn refresh_session(State(pool): State<PgPool>, State(auth): State<AuthConfig>, Json(input): Json<RefreshRequest>)
+    -> Result<Json<LoginResponse>, (StatusCode, String)>
+{
+    let rotated = sessions::rotate(&pool, &input.refresh_token)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let Some(session) = rotated else {
+        let reused = sessions::revoke_reused(&pool, &input.refresh_token)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        if let Some(session_id) = reused {
+            tracing::warn!(%session_id, "rotated refresh token reused; session revoked");
+        }
+        return Err((StatusCode::UNAUTHORIZED, "Invalid or expired refresh token".to_string()));
+    };
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(session.user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "User not found".to_string()))?;
+
+    Ok(Json(token_response(&auth, user, session)?))
+}
+
+async fn logout(State(pool): State<PgPool>, session: AuthSession)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    sessions::revoke(&pool, session.session_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if let Some(impersonator_id) = session.impersonator_id {
+        tracing::warn!(%impersonator_id, user_id = %session.user.id, session_id = %session.session_id, "impersonation ended");
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// === Self-service password reset (public) ===
+#[derive(Deserialize)]
+struct ForgotPasswordRequest { email: String }
+
+async fn forgot_password(
+    State(pool): State<PgPool>,
+    State(reset): State<ResetConfig>,
+    State(mailer): State<Arc<dyn Mailer>>,
+    Json(input): Json<ForgotPasswordRequest>,
+) -> StatusCode
+{
+    // Answer the same way (and just as fast) whether or not the account exists
+    send_reset_link(pool, reset, mailer, input.email);
+    StatusCode::ACCEPTED
+}
+
+// Emails the reset link, if the account has one, in the background
+fn send_reset_link(pool: PgPool, reset: ResetConfig, mailer: Arc<dyn Mailer>, email: String) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        if let Err(e) = password_reset::request(&pool, &reset, mailer.as_ref(), &email).await {
+            tracing::error!(error = %e, "failed to send password reset email");
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct ResetPasswordRequest { token: String, new_password: String }
+
+async fn reset_password(
+    State(pool): State<PgPool>,
+    State(passwords): State<PasswordPolicy>,
+    Json(input): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, PasswordChangeError> {
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let user_id = password_reset::consume(&mut *tx, &input.token)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid or expired reset token".to_string()))?;
+
+    // A rejected password rolls back the transaction, so the token stays usable
+    enforce_password_policy(&passwords, &pool, Some(user_id), &input.new_password, &[]).await?;
+    let hashed_password = password::hash(&input.new_password)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // A successful reset also lifts any login lockout
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET hashed_password = $2, failed_login_attempts = 0, locked_until = NULL
+        WHERE id = $1
+        "#
+    )
+    .bind(user_id)
+    .bind(&hashed_password)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    passwords
+        .remember(&mut tx, user_id, &hashed_password)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    sessions::revoke_all_for_user(&mut *tx, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Admin: force-logout a user everywhere
+async fn revoke_user_sessions(State(pool): State<PgPool>, user: User, Path(user_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    authorize(&user, Action::Update, &Resource::Users)?;
+    sessions::revoke_all_for_user(&pool, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn create_user(State(pool): State<PgPool>, State(passwords): State<PasswordPolicy>, user: User, Json(input): Json<NewUser>)
+    -> Result<Json<User>, PasswordChangeError>
+{
+    authorize(&user, Action::Create, &Resource::Users)?;
+    if input.roles.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "A user needs at least one role".to_string()).into());
+    }
+
+    enforce_password_policy(&passwords, &pool, None, &input.password, &[&input.email, &input.first_name, &input.last_name]).await?;
+    let hashed_password = password::hash(&input.password)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let row = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users (email, hashed_password, roles, first_name, last_name, school_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#
+    )
+    .bind(&input.email)
+    .bind(&hashed_password)
+    .bind(Role::normalize(input.roles))
+    .bind(&input.first_name)
+    .bind(&input.last_name)
+    .bind(input.school_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "Email is already registered".to_string())
+        }
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+            (StatusCode::BAD_REQUEST, "school_id must refer to a school".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    passwords
+        .remember(&mut tx, row.id, &hashed_password)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(row))
+}
+
+async fn list_users(State(pool): State<PgPool>, user: User, Query(filter): Query<SchoolFilter>)
+    -> Result<Json<Vec<User>>, (StatusCode, String)>
+{
+    authorize(&user, Action::Read, &Resource::Users)?;
+    let sql = format!("SELECT * FROM users WHERE ($2::uuid IS NULL OR school_id = $2) AND {}", tenant_condition(&user, "school_id"));
+    let users = sqlx::query_as::<_, User>(&sql)
+        .bind(user.id)
+        .bind(filter.school_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(users))
+}
+
+// Tells a field sent as null (Some(None)) apart from one left out (None)
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize)]
+struct UpdateUser {
+    email: Option<String>,
+    password: Option<String>,
+    roles: Option<Vec<Role>>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    // `"school_id": null` takes the user out of their school
+    #[serde(default, deserialize_with = "present")]
+    school_id: Option<Option<Uuid>>,
+}
+
+async fn update_user(
+    State(pool): State<PgPool>,
+    State(passwords): State<PasswordPolicy>,
+    user: User,
+    Path(user_id): Path<Uuid>,
+    Json(input): Json<UpdateUser>,
+) -> Result<Json<User>, PasswordChangeError> {
+    authorize(&user, Action::Update, &Resource::Users)?;
+    let roles = input.roles.map(Role::normalize);
+    if roles.as_ref().is_some_and(|roles| roles.is_empty()) {
+        return Err((StatusCode::BAD_REQUEST, "A user needs at least one role".to_string()).into());
+    }
+
+    let hashed_password = match &input.password {
+        Some(new_password) => {
+            let identity: Vec<&str> = [&input.email, &input.first_name, &input.last_name].into_iter().flatten().map(String::as_str).collect();
+            enforce_password_policy(&passwords, &pool, Some(user_id), new_password, &identity).await?;
+            Some(password::hash(new_password).await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?)
+        }
+        None => None,
+    };
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let (previous_roles, provider) = sqlx::query_as::<_, (Vec<Role>, String)>("SELECT roles, auth_provider FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+    if input.password.is_some() && provider != auth_provider::LOCAL {
+        return Err((StatusCode::CONFLICT, "The password of this account is managed by the school directory".to_string()).into());
+    }
+
+    // A new password or change of roles invalidates everything the user is currently logged in with
+    let revoke_sessions = input.password.is_some()
+        || roles.as_ref().is_some_and(|roles| *roles != previous_roles);
+
+    let updated_user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET
+            email = COALESCE($1, email),
+            hashed_password = COALESCE($2, hashed_password),
+            roles = COALESCE($3, roles),
+            first_name = COALESCE($4, first_name),
+            last_name = COALESCE($5, last_name),
+            school_id = CASE WHEN $6 THEN $8 ELSE school_id END
+        WHERE id = $7
+        RETURNING *
+        "#
+    )
+    .bind(input.email)
+    .bind(&hashed_password)
+    .bind(roles)
+    .bind(input.first_name)
+    .bind(input.last_name)
+    .bind(input.school_id.is_some())
+    .bind(user_id)
+    .bind(input.school_id.flatten())
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "Email is already registered".to_string())
+        }
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+            (StatusCode::BAD_REQUEST, "school_id must refer to a school".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    if let Some(hashed_password) = &hashed_password {
+        passwords
+            .remember(&mut tx, user_id, hashed_password)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    if revoke_sessions {
+        sessions::revoke_all_for_user(&mut *tx, user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(updated_user))
+}
+
+
+async fn delete_user(State(pool): State<PgPool>, user: User, Path(user_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    authorize(&user, Action::Delete, &Resource::Users)?;
+    // The user's sessions are removed with it (ON DELETE CASCADE), which logs them out everywhere
+    let result = sqlx::query!("DELETE FROM users WHERE id = $1", user_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if result.rows_affected() == 0 {
+        Err((StatusCode::NOT_FOUND, "User not found".into()))
+    } else {
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
+
+// Admin: lift a login lockout early
+async fn unlock_user(State(pool): State<PgPool>, user: User, Path(user_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    authorize(&user, Action::Update, &Resource::Users)?;
+    let found = lockout::unlock(&pool, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if found {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "User not found".into()))
+    }
+}
+
+// === Impersonation ("view as user"): Admin only ===
+#[derive(Serialize)]
+struct ImpersonationResponse {
+    user_id: Uuid,
+    role: Role,
+    roles: Vec<Role>,
+    impersonator_id: Uuid,
+    access_token: String,
+    token_type: &'static str,
+    expires_in: i64,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+async fn impersonate_user(
+    State(pool): State<PgPool>,
+    State(auth): State<AuthConfig>,
+    State(config): State<ImpersonationConfig>,
+    session: AuthSession,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<ImpersonationResponse>, (StatusCode, String)> {
+    let admin = session.user;
+
+    let target = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "User not found".to_string()))?;
+    authorize(&admin, Action::Impersonate, &Resource::User(&target.roles))?;
+
+    let (session_id, expires_at) = impersonation::start(&pool, admin.id, target.id, config.ttl)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    // Logged like the requests made under it; logging out ends it and is logged the same way
+    impersonation::record(&pool, session_id, admin.id, target.id, "POST", &format!("/users/{}/impersonate", target.id), true)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let access_token = auth
+        .issue_impersonation_token(&target, session_id, admin.id, expires_at)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::warn!(impersonator_id = %admin.id, user_id = %target.id, %session_id, "impersonation started");
+
+    Ok(Json(ImpersonationResponse {
+        user_id: target.id,
+        role: target.primary_role(),
+        roles: target.roles,
+        impersonator_id: admin.id,
+        access_token,
+        token_type: "Bearer",
+        expires_in: config.ttl.num_seconds(),
+        expires_at,
+    }))
+}
+
+#[derive(Deserialize)]
+struct ImpersonationLogQuery { user_id: Option<Uuid>, limit: Option<i64> }
+
+// Entries where the given user was either the admin or the impersonated user
+async fn impersonation_log(State(pool): State<PgPool>, session: AuthSession, Query(query): Query<ImpersonationLogQuery>)
+    -> Result<Json<Vec<impersonation::LogEntry>>, (StatusCode, String)>
+{
+    authorize(&session.user, Action::Read, &Resource::ImpersonationLog)?;
+    let entries = impersonation::recent(&pool, query.user_id, query.limit.unwrap_or(200).clamp(1, 1000))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(entries))
+}
+
+// === Own profile: any authenticated role ===
+async fn get_me(user: User) -> Json<User> {
+    Json(user)
+}
+
+#[derive(Deserialize)]
+struct UpdateMe {
+    email: Option<String>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    current_password: Option<String>,
+    new_password: Option<String>,
+}
+
+async fn update_me(
+    State(pool): State<PgPool>,
+    State(passwords): State<PasswordPolicy>,
+    session: AuthSession,
+    Json(input): Json<UpdateMe>,
+) -> Result<Json<User>, PasswordChangeError> {
+    let user = session.user;
+
+    // Whoever controls the email controls password resets, so it is guarded like the password
+    if input.email.is_some() || input.new_password.is_some() {
+        if user.auth_provider != auth_provider::LOCAL {
+            return Err((StatusCode::CONFLICT, "Email and password of this account are managed by the school directory".to_string()).into());
+        }
+        let current_password = input.current_password.as_deref().ok_or_else(|| {
+            (StatusCode::BAD_REQUEST, "current_password is required to change email or password".to_string())
+        })?;
+        let password_ok = password::verify_user(&pool, user.id, current_password)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        if !password_ok {
+            return Err((StatusCode::FORBIDDEN, "Current password is incorrect".to_string()).into());
+        }
+    }
+
+    let hashed_password = match &input.new_password {
+        Some(new_password) => {
+            let identity: Vec<&str> = [&input.email, &input.first_name, &input.last_name].into_iter().flatten().map(String::as_str).collect();
+            enforce_password_policy(&passwords, &pool, Some(user.id), new_password, &identity).await?;
+            Some(password::hash(new_password).await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?)
+        }
+        None => None,
+    };
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let updated_user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET
+            email = COALESCE($1, email),
+            hashed_password = COALESCE($2, hashed_password),
+            first_name = COALESCE($3, first_name),
+            last_name = COALESCE($4, last_name)
+        WHERE id = $5
+        RETURNING *
+        "#
+    )
+    .bind(input.email)
+    .bind(&hashed_password)
+    .bind(input.first_name)
+    .bind(input.last_name)
+    .bind(user.id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "Email is already registered".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    // Keep the session that made the change, drop every other one
+    if let Some(hashed_password) = &hashed_password {
+        passwords
+            .remember(&mut tx, user.id, hashed_password)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        sessions::revoke_others(&mut *tx, user.id, session.session_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(updated_user))
+}
+
+// === API keys: Admin only, minted and managed from a human login ===
+async fn create_api_key(State(pool): State<PgPool>, session: AuthSession, Json(input): Json<NewApiKey>)
+    -> Result<(StatusCode, Json<CreatedApiKey>), (StatusCode, String)>
+{
+    let user = session.user;
+    authorize(&user, Action::Create, &Resource::ApiKeys)?;
+    if input.name.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "name must not be empty".to_string()));
+    }
+    if input.scopes.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "At least one scope is required".to_string()));
+    }
+    if let Some(scope) = input.scopes.iter().find(|scope| !api_keys::is_valid_scope(scope)) {
+        return Err((StatusCode::BAD_REQUEST, format!("Unknown scope {}", scope)));
+    }
+
+    let key = api_keys::generate_key();
+    let expires_in_days = input
+        .expires_in_days
+        .unwrap_or(api_keys::DEFAULT_TTL_DAYS)
+        .clamp(1, api_keys::MAX_TTL_DAYS);
+    let api_key = sqlx::query_as::<_, ApiKey>(
+        r#"
+        INSERT INTO api_keys (name, key_hash, scopes, school_id, created_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, now() + make_interval(days => $6))
+        RETURNING id, name, scopes, school_id, created_by, created_at, expires_at, last_used_at, revoked_at
+        "#
+    )
+    .bind(input.name.trim())
+    .bind(api_keys::hash_key(&key))
+    .bind(&input.scopes)
+    .bind(input.school_id)
+    .bind(user.id)
+    .bind(expires_in_days as i32)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+            (StatusCode::BAD_REQUEST, "school_id must refer to a school".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    tracing::info!(api_key_id = %api_key.id, name = %api_key.name, created_by = %user.id, "API key created");
+    Ok((StatusCode::CREATED, Json(CreatedApiKey { api_key, key })))
+}
+
+async fn list_api_keys(State(pool): State<PgPool>, session: AuthSession)
+    -> Result<Json<Vec<ApiKey>>, (StatusCode, String)>
+{
+    authorize(&session.user, Action::Read, &Resource::ApiKeys)?;
+    let keys = sqlx::query_as::<_, ApiKey>(
+        r#"
+        SELECT id, name, scopes, school_id, created_by, created_at, expires_at, last_used_at, revoked_at
+        FROM api_keys ORDER BY created_at DESC
+        "#
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(keys))
+}
+
+// Revoked keys are kept (with revoked_at set) so their history stays visible.
+async fn revoke_api_key(State(pool): State<PgPool>, session: AuthSession, Path(key_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    authorize(&session.user, Action::Delete, &Resource::ApiKeys)?;
+    let result = sqlx::query("UPDATE api_keys SET revoked_at = now() WHERE id = $1 AND revoked_at IS NULL")
+        .bind(key_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if result.rows_affected() == 0 {
+        Err((StatusCode::NOT_FOUND, "API key not found or already revoked".into()))
+    } else {
+        tracing::info!(api_key_id = %key_id, revoked_by = %session.user.id, "API key revoked");
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
+
+// === Invites & registration ===
+// Admins may invite any role; directors everyone below them.
+async fn create_invite(State(pool): State<PgPool>, user: User, Json(input): Json<NewInvite>)
+    -> Result<Json<CreatedInvite>, (StatusCode, String)>
+{
+    authorize(&user, Action::Create, &Resource::Invite(&input.role))?;
+    if input.student_id.is_some() && input.role != Role::Parent {
+        return Err((StatusCode::BAD_REQUEST, "Only parent invites can be bound to a student".to_string()));
+    }
+    let student_school_id = match input.student_id {
+        Some(student_id) => {
+            let school_id = student_facts(&pool, &user, student_id).await?.school_id;
+            authorize_school(&user, school_id)?;
+            school_id
+        }
+        None => None,
+    };
+    let school_id = input.school_id.or(student_school_id).or(user.school_id);
+    authorize_school(&user, school_id)?;
+
+    let code = invites::generate_code();
+    let expires_in_days = input.expires_in_days.unwrap_or(invites::DEFAULT_TTL_DAYS).clamp(1, 90);
+    let invite = sqlx::query_as::<_, Invite>(
+        r#"
+        INSERT INTO invites (code_hash, role, student_id, school_id, created_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, now() + make_interval(days => $6))
+        RETURNING id, role, student_id, school_id, created_by, created_at, expires_at, redeemed_at, redeemed_by
+        "#
+    )
+    .bind(invites::hash_code(&code))
+    .bind(&input.role)
+    .bind(input.student_id)
+    .bind(school_id)
+    .bind(user.id)
+    .bind(expires_in_days as i32)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+            (StatusCode::BAD_REQUEST, "school_id must refer to a school".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    Ok(Json(CreatedInvite { invite, code }))
+}
+
+async fn list_invites(State(pool): State<PgPool>, user: User, Query(filter): Query<SchoolFilter>)
+    -> Result<Json<Vec<Invite>>, (StatusCode, String)>
+{
+    authorize(&user, Action::Read, &Resource::Invites)?;
+    let sql = format!(
+        r#"
+        SELECT id, role, student_id, school_id, created_by, created_at, expires_at, redeemed_at, redeemed_by
+        FROM invites WHERE ($2::uuid IS NULL OR school_id = $2) AND {} ORDER BY created_at DESC
+        "#,
+        tenant_condition(&user, "school_id")
+    );
+    let invites = sqlx::query_as::<_, Invite>(&sql)
+        .bind(user.id)
+        .bind(filter.school_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(invites))
+}
+
+async fn delete_invite(State(pool): State<PgPool>, user: User, Path(invite_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    authorize(&user, Action::Delete, &Resource::Invites)?;
+    let sql = format!("DELETE FROM invites WHERE id = $2 AND redeemed_at IS NULL AND {}", tenant_condition(&user, "school_id"));
+    let result = sqlx::query(&sql)
+        .bind(user.id)
+        .bind(invite_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if result.rows_affected() == 0 {
+        Err((StatusCode::NOT_FOUND, "Invite not found or already redeemed".into()))
+    } else {
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
+
+#[derive(Serialize)]
+struct RegisterResponse { user_id: Uuid, email: String, role: Role }
+
+// Public: redeem an invite code. The user row, the parent-student link (for
+// parent invites bound to a student) and the redemption happen in one transaction.
+async fn register(State(pool): State<PgPool>, State(passwords): State<PasswordPolicy>, Json(input): Json<Registration>)
+    -> Result<(StatusCode, Json<RegisterResponse>), PasswordChangeError>
+{
+    enforce_password_policy(&passwords, &pool, None, &input.password, &[&input.email, &input.first_name, &input.last_name]).await?;
+    let hashed_password = password::hash(&input.password)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let invite = sqlx::query_as::<_, Invite>(
+        r#"
+        SELECT id, role, student_id, school_id, created_by, created_at, expires_at, redeemed_at, redeemed_by
+        FROM invites
+        WHERE code_hash = $1 AND redeemed_at IS NULL AND expires_at > now()
+        FOR UPDATE
+        "#
+    )
+    .bind(invites::hash_code(&input.code))
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid or expired invite code".to_string()))?;
+
+    let new_user = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users (email, hashed_password, roles, first_name, last_name, school_id)
+        VALUES ($1, $2, ARRAY[$3], $4, $5, $6)
+        RETURNING *
+        "#
+    )
+    .bind(&input.email)
+    .bind(&hashed_password)
+    .bind(&invite.role)
+    .bind(&input.first_name)
+    .bind(&input.last_name)
+    .bind(invite.school_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "Email is already registered".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+
+    passwords
+        .remember(&mut tx, new_user.id, &hashed_password)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if let Some(student_id) = invite.student_id {
+        sqlx::query("INSERT INTO parent_students (parent_id, student_id) VALUES ($1, $2)")
+            .bind(new_user.id)
+            .bind(student_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    sqlx::query("UPDATE invites SET redeemed_at = now(), redeemed_by = $2 WHERE id = $1")
+        .bind(invite.id)
+        .bind(new_user.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok((StatusCode::CREATED, Json(RegisterResponse { user_id: new_user.id, email: new_user.email, role: invite.role })))
+}
+
+// === Schools: Admin manages, everyone else sees their own ===
+async fn create_school(State(pool): State<PgPool>, user: User, Json(input): Json<NewSchool>)
+    -> Result<(StatusCode, Json<School>), (StatusCode, String)>
+{
+    authorize(&user, Action::Create, &Resource::Schools)?;
+    if input.name.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "name must not be empty".to_string()));
+    }
+    let school = sqlx::query_as::<_, School>(
+        "INSERT INTO schools (name, address) VALUES ($1, $2) RETURNING *"
+    )
+    .bind(input.name.trim())
+    .bind(input.address)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "A school with this name already exists".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+    Ok((StatusCode::CREATED, Json(school)))
+}
+
+async fn list_schools(State(pool): State<PgPool>, user: User)
+    -> Result<Json<Vec<School>>, (StatusCode, String)>
+{
+    authorize(&user, Action::Read, &Resource::Schools)?;
+    let sql = format!("SELECT * FROM schools WHERE {} ORDER BY name", tenant_condition(&user, "id"));
+    let schools = sqlx::query_as::<_, School>(&sql)
+        .bind(user.id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(schools))
+}
+
+async fn get_school(State(pool): State<PgPool>, user: User, Path(school_id): Path<Uuid>)
+    -> Result<Json<School>, (StatusCode, String)>
+{
+    authorize(&user, Action::Read, &Resource::Schools)?;
+    authorize_school(&user, Some(school_id))?;
+    sqlx::query_as::<_, School>("SELECT * FROM schools WHERE id = $1")
+        .bind(school_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "School not found".to_string()))
+}
+
+#[derive(Deserialize)]
+struct UpdateSchool { name: Option<String>, address: Option<String> }
+
+async fn update_school(State(pool): State<PgPool>, user: User, Path(school_id): Path<Uuid>, Json(input): Json<UpdateSchool>)
+    -> Result<Json<School>, (StatusCode, String)>
+{
+    authorize(&user, Action::Update, &Resource::Schools)?;
+    let name = input.name.as_deref().map(str::trim);
+    if name.is_some_and(str::is_empty) {
+        return Err((StatusCode::BAD_REQUEST, "name must not be empty".to_string()));
+    }
+    sqlx::query_as::<_, School>(
+        "UPDATE schools SET name = COALESCE($1, name), address = COALESCE($2, address) WHERE id = $3 RETURNING *"
+    )
+    .bind(name)
+    .bind(input.address)
+    .bind(school_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "A school with this name already exists".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?
+    .map(Json)
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "School not found".to_string()))
+}
+
+async fn delete_school(State(pool): State<PgPool>, user: User, Path(school_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    authorize(&user, Action::Delete, &Resource::Schools)?;
+    let result = sqlx::query("DELETE FROM schools WHERE id = $1")
+        .bind(school_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                (StatusCode::CONFLICT, "School still has users or students".to_string())
+            }
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+    if result.rows_affected() == 0 {
+        Err((StatusCode::NOT_FOUND, "School not found".into()))
+    } else {
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
+
+// === Classes: Admin/Director manage, everyone sees their school's ===
+// "2025/2026": a year and the one after it
+fn is_academic_year(value: &str) -> bool {
+    match value.split_once('/') {
+        Some((start, end)) if start.len() == 4 && end.len() == 4 => {
+            matches!((start.parse::<u16>(), end.parse::<u16>()), (Ok(start), Ok(end)) if end == start + 1)
+        }
+        _ => false,
+    }
+}
+
+// Normalized parallel letter ("a " -> "A"), or None when it isn't one
+fn class_letter(value: &str) -> Option<String> {
+    let letter = value.trim().to_uppercase();
+    (!letter.is_empty() && letter.chars().all(char::is_alphabetic)).then_some(letter)
+}
+
+// Homeroom teachers have to be teachers of the class's school
+async fn check_homeroom_teacher(pool: &PgPool, teacher_id: Uuid, school_id: Uuid) -> Result<(), (StatusCode, String)> {
+    if !is_teacher(pool, teacher_id).await? {
+        return Err((StatusCode::BAD_REQUEST, "homeroom_teacher_id must refer to a teacher".to_string()));
+    }
+    if school_of_user(pool, teacher_id).await? != Some(school_id) {
+        return Err((StatusCode::BAD_REQUEST, "The homeroom teacher belongs to another school".to_string()));
+    }
+    Ok(())
+}
+
+// A class's academic year has to be one of its school's
+async fn check_class_year(pool: &PgPool, year_id: Uuid, school_id: Uuid) -> Result<(), (StatusCode, String)> {
+    let year_school_id = sqlx::query_scalar::<_, Uuid>("SELECT school_id FROM academic_years WHERE id = $1")
+        .bind(year_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "academic_year_id must refer to an academic year".to_string()))?;
+    if year_school_id != school_id {
+        return Err((StatusCode::BAD_REQUEST, "The academic year belongs to another school".to_string()));
+    }
+    Ok(())
+}
+
+async fn create_class(State(pool): State<PgPool>, user: User, Json(input): Json<NewClass>)
+    -> Result<(StatusCode, Json<Class>), (StatusCode, String)>
+{
+    authorize(&user, Action::Create, &Resource::Classes)?;
+    let school_id = input.school_id.or(user.school_id)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "school_id is required".to_string()))?;
+    authorize_school(&user, Some(school_id))?;
+    if !(1..=12).contains(&input.grade_level) {
+        return Err((StatusCode::BAD_REQUEST, "grade_level must be between 1 and 12".to_string()));
+    }
+    let letter = class_letter(&input.letter)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "letter must consist of letters only".to_string()))?;
+    check_class_year(&pool, input.academic_year_id, school_id).await?;
+    if let Some(teacher_id) = input.homeroom_teacher_id {
+        check_homeroom_teacher(&pool, teacher_id, school_id).await?;
+    }
+
+    let class = sqlx::query_as::<_, Class>(
+        r#"
+        INSERT INTO classes (school_id, grade_level, letter, academic_year_id, homeroom_teacher_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#
+    )
+    .bind(school_id)
+    .bind(input.grade_level)
+    .bind(letter)
+    .bind(input.academic_year_id)
+    .bind(input.homeroom_teacher_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "This class already exists in that school and year".to_string())
+        }
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+            (StatusCode::BAD_REQUEST, "school_id must refer to a school".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+    Ok((StatusCode::CREATED, Json(class)))
+}
+
+#[derive(Deserialize)]
+struct ClassQuery { school_id: Option<Uuid>, academic_year_id: Option<Uuid> }
+
+async fn list_classes(State(pool): State<PgPool>, user: User, Query(query): Query<ClassQuery>)
+    -> Result<Json<Vec<Class>>, (StatusCode, String)>
+{
+    authorize(&user, Action::Read, &Resource::Classes)?;
+    let sql = format!(
+        r#"
+        SELECT c.* FROM classes c JOIN academic_years y ON y.id = c.academic_year_id
+        WHERE ($2::uuid IS NULL OR c.school_id = $2) AND ($3::uuid IS NULL OR c.academic_year_id = $3) AND {}
+        ORDER BY y.starts_on DESC, c.grade_level, c.letter
+        "#,
+        tenant_condition(&user, "c.school_id")
+    );
+    let classes = sqlx::query_as::<_, Class>(&sql)
+        .bind(user.id)
+        .bind(query.school_id)
+        .bind(query.academic_year_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(classes))
+}
+
+async fn get_class(State(pool): State<PgPool>, user: User, Path(class_id): Path<Uuid>)
+    -> Result<Json<Class>, (StatusCode, String)>
+{
+    let class = class_facts(&pool, &user, class_id).await?;
+    authorize(&user, Action::Read, &Resource::Class(&class))?;
+    let class = sqlx::query_as::<_, Class>("SELECT * FROM classes WHERE id = $1")
+        .bind(class_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(class))
+}
+
+#[derive(Deserialize)]
+struct UpdateClass {
+    grade_level: Option<i16>,
+    letter: Option<String>,
+    academic_year_id: Option<Uuid>,
+    homeroom_teacher_id: Option<Uuid>,
+}
+
+async fn update_class(State(pool): State<PgPool>, user: User, Path(class_id): Path<Uuid>, Json(input): Json<UpdateClass>)
+    -> Result<Json<Class>, (StatusCode, String)>
+{
+    let class = class_facts(&pool, &user, class_id).await?;
+    authorize(&user, Action::Update, &Resource::Class(&class))?;
+    if input.grade_level.is_some_and(|grade_level| !(1..=12).contains(&grade_level)) {
+        return Err((StatusCode::BAD_REQUEST, "grade_level must be between 1 and 12".to_string()));
+    }
+    let letter = match &input.letter {
+        Some(letter) => Some(class_letter(letter)
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, "letter must consist of letters only".to_string()))?),
+        None => None,
+    };
+    if let Some(year_id) = input.academic_year_id {
+        check_class_year(&pool, year_id, class.school_id).await?;
+    }
+    if let Some(teacher_id) = input.homeroom_teacher_id {
+        check_homeroom_teacher(&pool, teacher_id, class.school_id).await?;
+    }
+
+    let class = sqlx::query_as::<_, Class>(
+        r#"
+        UPDATE classes
+        SET
+            grade_level = COALESCE($1, grade_level),
+            letter = COALESCE($2, letter),
+            academic_year_id = COALESCE($3, academic_year_id),
+            homeroom_teacher_id = COALESCE($4, homeroom_teacher_id)
+        WHERE id = $5
+        RETURNING *
+        "#
+    )
+    .bind(input.grade_level)
+    .bind(letter)
+    .bind(input.academic_year_id)
+    .bind(input.homeroom_teacher_id)
+    .bind(class_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "This class already exists in that school and year".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+    Ok(Json(class))
+}
+
+async fn delete_class(State(pool): State<PgPool>, user: User, Path(class_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    let class = class_facts(&pool, &user, class_id).await?;
+    authorize(&user, Action::Delete, &Resource::Class(&class))?;
+    // Its curriculum goes with it; students have to be moved out first
+    sqlx::query("DELETE FROM classes WHERE id = $1")
+        .bind(class_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                (StatusCode::CONFLICT, "Class still has students".to_string())
+            }
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// The students of a class, by name: staff, its homeroom teacher and the teachers it has by its curriculum
+async fn class_roster(State(pool): State<PgPool>, user: User, Path(class_id): Path<Uuid>)
+    -> Result<Json<Vec<Student>>, (StatusCode, String)>
+{
+    let class = class_facts(&pool, &user, class_id).await?;
+    authorize(&user, Action::Read, &Resource::ClassRoster(&class))?;
+    let students = sqlx::query_as::<_, Student>(
+        &format!("{} WHERE s.class_id = $1 ORDER BY u.last_name, u.first_name", STUDENT_SELECT)
+    )
+    .bind(class_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(students))
+}
+
+// === Academic years & terms: Admin/Director manage, everyone sees their school's ===
+// (Re)attaches the grades and absences of the school's students dated within
+// the given ranges to the term their date falls in; run with the old and new
+// dates of a term whenever they change. Records outside are left alone.
+async fn attach_to_terms(conn: &mut sqlx::PgConnection, school_id: Uuid, ranges: &[(chrono::NaiveDate, chrono::NaiveDate)])
+    -> Result<(), (StatusCode, String)>
+{
+    let grades = format!(
+        "UPDATE grades g SET term_id = {} FROM students s \
+         WHERE s.id = g.student_id AND s.school_id = $1 AND (g.created_at AT TIME ZONE 'UTC')::date BETWEEN $2 AND $3",
+        term_for("s.school_id", "(g.created_at AT TIME ZONE 'UTC')::date")
+    );
+    let absences = format!(
+        "UPDATE absences a SET term_id = {} FROM students s \
+         WHERE s.id = a.student_id AND s.school_id = $1 AND a.date BETWEEN $2 AND $3",
+        term_for("s.school_id", "a.date")
+    );
+    for (starts_on, ends_on) in ranges {
+        for sql in [&grades, &absences] {
+            sqlx::query(sql)
+                .bind(school_id)
+                .bind(starts_on)
+                .bind(ends_on)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+async fn fetch_academic_year(pool: &PgPool, year_id: Uuid) -> Result<AcademicYear, (StatusCode, String)> {
+    sqlx::query_as::<_, AcademicYear>("SELECT * FROM academic_years WHERE id = $1")
+        .bind(year_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Academic year not found".to_string()))
+}
+
+async fn fetch_term(pool: &PgPool, term_id: Uuid) -> Result<Term, (StatusCode, String)> {
+    sqlx::query_as::<_, Term>("SELECT * FROM terms WHERE id = $1")
+        .bind(term_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Term not found".to_string()))
+}
+
+// Years of a school may not overlap, and an existing year must still hold its terms.
+async fn check_year_dates(pool: &PgPool, school_id: Uuid, year_id: Option<Uuid>, starts_on: chrono::NaiveDate, ends_on: chrono::NaiveDate)
+    -> Result<(), (StatusCode, String)>
+{
+    if starts_on >= ends_on {
+        return Err((StatusCode::BAD_REQUEST, "starts_on must be before ends_on".to_string()));
+    }
+    let (overlaps, terms_outside) = sqlx::query_as::<_, (bool, bool)>(
+        r#"
+        SELECT
+            EXISTS (SELECT 1 FROM academic_years WHERE school_id = $1 AND id IS DISTINCT FROM $2 AND starts_on <= $4 AND ends_on >= $3),
+            EXISTS (SELECT 1 FROM terms WHERE academic_year_id = $2 AND (starts_on < $3 OR ends_on > $4))
+        "#
+    )
+    .bind(school_id)
+    .bind(year_id)
+    .bind(starts_on)
+    .bind(ends_on)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if overlaps {
+        return Err((StatusCode::CONFLICT, "The dates overlap another academic year of the school".to_string()));
+    }
+    if terms_outside {
+        return Err((StatusCode::BAD_REQUEST, "The year's terms have to lie within its dates".to_string()));
+    }
+    Ok(())
+}
+
+// Terms lie within their year and don't overlap each other, so every day is in at most one term.
+async fn check_term_dates(pool: &PgPool, year: &AcademicYear, term_id: Option<Uuid>, starts_on: chrono::NaiveDate, ends_on: chrono::NaiveDate)
+    -> Result<(), (StatusCode, String)>
+{
+    if starts_on > ends_on {
+        return Err((StatusCode::BAD_REQUEST, "starts_on must not be after ends_on".to_string()));
+    }
+    if starts_on < year.starts_on || ends_on > year.ends_on {
+        return Err((StatusCode::BAD_REQUEST, "A term has to lie within its academic year".to_string()));
+    }
+    let overlaps = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM terms WHERE academic_year_id = $1 AND id IS DISTINCT FROM $2 AND starts_on <= $4 AND ends_on >= $3)"
+    )
+    .bind(year.id)
+    .bind(term_id)
+    .bind(starts_on)
+    .bind(ends_on)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if overlaps {
+        return Err((StatusCode::CONFLICT, "The dates overlap another term of the year".to_string()));
+    }
+    Ok(())
+}
+
+async fn create_academic_year(State(pool): State<PgPool>, user: User, Json(input): Json<NewAcademicYear>)
+    -> Result<(StatusCode, Json<AcademicYear>), (StatusCode, String)>
+{
+    authorize(&user, Action::Create, &Resource::AcademicYears)?;
+    let school_id = input.school_id.or(user.school_id)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "school_id is required".to_string()))?;
+    authorize_school(&user, Some(school_id))?;
+    if !is_academic_year(&input.name) {
+        return Err((StatusCode::BAD_REQUEST, "name must look like 2025/2026".to_string()));
+    }
+    check_year_dates(&pool, school_id, None, input.starts_on, input.ends_on).await?;
+
+    let year = sqlx::query_as::<_, AcademicYear>(
+        "INSERT INTO academic_years (school_id, name, starts_on, ends_on) VALUES ($1, $2, $3, $4) RETURNING *"
+    )
+    .bind(school_id)
+    .bind(&input.name)
+    .bind(input.starts_on)
+    .bind(input.ends_on)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "This academic year already exists in that school".to_string())
+        }
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+            (StatusCode::BAD_REQUEST, "school_id must refer to a school".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+    Ok((StatusCode::CREATED, Json(year)))
+}
+
+async fn list_academic_years(State(pool): State<PgPool>, user: User, Query(filter): Query<SchoolFilter>)
+    -> Result<Json<Vec<AcademicYear>>, (StatusCode, String)>
+{
+    authorize(&user, Action::Read, &Resource::AcademicYears)?;
+    let sql = format!(
+        "SELECT * FROM academic_years WHERE ($2::uuid IS NULL OR school_id = $2) AND {} ORDER BY starts_on DESC",
+        tenant_condition(&user, "school_id")
+    );
+    let years = sqlx::query_as::<_, AcademicYear>(&sql)
+        .bind(user.id)
+        .bind(filter.school_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(years))
+}
+
+async fn get_academic_year(State(pool): State<PgPool>, user: User, Path(year_id): Path<Uuid>)
+    -> Result<Json<AcademicYear>, (StatusCode, String)>
+{
+    let year = fetch_academic_year(&pool, year_id).await?;
+    authorize(&user, Action::Read, &Resource::AcademicYear(year.school_id))?;
+    Ok(Json(year))
+}
+
+#[derive(Deserialize)]
+struct UpdateAcademicYear { name: Option<String>, starts_on: Option<chrono::NaiveDate>, ends_on: Option<chrono::NaiveDate> }
+
+async fn update_academic_year(State(pool): State<PgPool>, user: User, Path(year_id): Path<Uuid>, Json(input): Json<UpdateAcademicYear>)
+    -> Result<Json<AcademicYear>, (StatusCode, String)>
+{
+    let year = fetch_academic_year(&pool, year_id).await?;
+    authorize(&user, Action::Update, &Resource::AcademicYear(year.school_id))?;
+    if input.name.as_deref().is_some_and(|name| !is_academic_year(name)) {
+        return Err((StatusCode::BAD_REQUEST, "name must look like 2025/2026".to_string()));
+    }
+    let starts_on = input.starts_on.unwrap_or(year.starts_on);
+    let ends_on = input.ends_on.unwrap_or(year.ends_on);
+    check_year_dates(&pool, year.school_id, Some(year_id), starts_on, ends_on).await?;
+
+    // Terms stay within the year, so no grade or absence changes term here
+    let year = sqlx::query_as::<_, AcademicYear>(
+        "UPDATE academic_years SET name = COALESCE($1, name), starts_on = $2, ends_on = $3 WHERE id = $4 RETURNING *"
+    )
+    .bind(input.name)
+    .bind(starts_on)
+    .bind(ends_on)
+    .bind(year_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "This academic year already exists in that school".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+    Ok(Json(year))
+}
+
+// Deletes the year's terms too; their grades and absences are kept without a term.
+// Classes have to be moved to another year or deleted first.
+async fn delete_academic_year(State(pool): State<PgPool>, user: User, Path(year_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    let year = fetch_academic_year(&pool, year_id).await?;
+    authorize(&user, Action::Delete, &Resource::AcademicYear(year.school_id))?;
+    sqlx::query("DELETE FROM academic_years WHERE id = $1")
+        .bind(year_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                (StatusCode::CONFLICT, "Academic year still has classes".to_string())
+            }
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn create_term(State(pool): State<PgPool>, user: User, Json(input): Json<NewTerm>)
+    -> Result<(StatusCode, Json<Term>), (StatusCode, String)>
+{
+    let year = fetch_academic_year(&pool, input.academic_year_id).await?;
+    authorize(&user, Action::Update, &Resource::AcademicYear(year.school_id))?;
+    let name = input.name.trim();
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "name is required".to_string()));
+    }
+    check_term_dates(&pool, &year, None, input.starts_on, input.ends_on).await?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let term = sqlx::query_as::<_, Term>(
+        "INSERT INTO terms (academic_year_id, name, starts_on, ends_on) VALUES ($1, $2, $3, $4) RETURNING *"
+    )
+    .bind(year.id)
+    .bind(name)
+    .bind(input.starts_on)
+    .bind(input.ends_on)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "A term with this name already exists in that year".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+    attach_to_terms(&mut tx, year.school_id, &[(term.starts_on, term.ends_on)]).await?;
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok((StatusCode::CREATED, Json(term)))
+}
+
+#[derive(Deserialize)]
+struct TermQuery { academic_year_id: Option<Uuid>, school_id: Option<Uuid> }
+
+async fn list_terms(State(pool): State<PgPool>, user: User, Query(query): Query<TermQuery>)
+    -> Result<Json<Vec<Term>>, (StatusCode, String)>
+{
+    authorize(&user, Action::Read, &Resource::AcademicYears)?;
+    let sql = format!(
+        r#"
+        SELECT t.* FROM terms t JOIN academic_years y ON y.id = t.academic_year_id
+        WHERE ($2::uuid IS NULL OR t.academic_year_id = $2) AND ($3::uuid IS NULL OR y.school_id = $3) AND {}
+        ORDER BY t.starts_on DESC
+        "#,
+        tenant_condition(&user, "y.school_id")
+    );
+    let terms = sqlx::query_as::<_, Term>(&sql)
+        .bind(user.id)
+        .bind(query.academic_year_id)
+        .bind(query.school_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(terms))
+}
+
+async fn get_term(State(pool): State<PgPool>, user: User, Path(term_id): Path<Uuid>)
+    -> Result<Json<Term>, (StatusCode, String)>
+{
+    let term = fetch_term(&pool, term_id).await?;
+    let year = fetch_academic_year(&pool, term.academic_year_id).await?;
+    authorize(&user, Action::Read, &Resource::AcademicYear(year.school_id))?;
+    Ok(Json(term))
+}
+
+#[derive(Deserialize)]
+struct UpdateTerm { name: Option<String>, starts_on: Option<chrono::NaiveDate>, ends_on: Option<chrono::NaiveDate> }
+
+async fn update_term(State(pool): State<PgPool>, user: User, Path(term_id): Path<Uuid>, Json(input): Json<UpdateTerm>)
+    -> Result<Json<Term>, (StatusCode, String)>
+{
+    let term = fetch_term(&pool, term_id).await?;
+    let year = fetch_academic_year(&pool, term.academic_year_id).await?;
+    authorize(&user, Action::Update, &Resource::AcademicYear(year.school_id))?;
+    let name = input.name.as_deref().map(str::trim);
+    if name == Some("") {
+        return Err((StatusCode::BAD_REQUEST, "name must not be empty".to_string()));
+    }
+    let starts_on = input.starts_on.unwrap_or(term.starts_on);
+    let ends_on = input.ends_on.unwrap_or(term.ends_on);
+    check_term_dates(&pool, &year, Some(term_id), starts_on, ends_on).await?;
+    let previous_dates = (term.starts_on, term.ends_on);
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let term = sqlx::query_as::<_, Term>(
+        "UPDATE terms SET name = COALESCE($1, name), starts_on = $2, ends_on = $3 WHERE id = $4 RETURNING *"
+    )
+    .bind(name)
+    .bind(starts_on)
+    .bind(ends_on)
+    .bind(term_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "A term with this name already exists in that year".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+    // Records that were in the term and those it now covers
+    attach_to_terms(&mut tx, year.school_id, &[previous_dates, (starts_on, ends_on)]).await?;
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(term))
+}
+
+// The term's grades and absences are kept without a term
+async fn delete_term(State(pool): State<PgPool>, user: User, Path(term_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    let term = fetch_term(&pool, term_id).await?;
+    let year = fetch_academic_year(&pool, term.academic_year_id).await?;
+    authorize(&user, Action::Update, &Resource::AcademicYear(year.school_id))?;
+    sqlx::query("DELETE FROM terms WHERE id = $1")
+        .bind(term_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// === Subject catalog: Admin manages, everyone reads ===
+// Normalized subject code ("math " -> "MATH"), or None when it isn't one
+fn subject_code(value: &str) -> Option<String> {
+    let code = value.trim().to_uppercase();
+    (!code.is_empty() && code.chars().all(|c| c.is_alphanumeric() || c == '_')).then_some(code)
+}
+
+// Trimmed names keyed by language code ("en", "bg", "pt-BR"); at least one
+fn subject_names(names: BTreeMap<String, String>) -> Result<BTreeMap<String, String>, (StatusCode, String)> {
+    let names: BTreeMap<String, String> = names
+        .into_iter()
+        .map(|(language, name)| (language.trim().to_string(), name.trim().to_string()))
+        .collect();
+    let valid_language = |language: &str| {
+        let (base, region) = language.split_once('-').unwrap_or((language, ""));
+        (2..=3).contains(&base.len()) && base.chars().all(|c| c.is_ascii_lowercase())
+            && (region.is_empty() || (region.len() == 2 && region.chars().all(|c| c.is_ascii_uppercase())))
+    };
+    if names.is_empty() || names.iter().any(|(language, name)| !valid_language(language) || name.is_empty()) {
+        return Err((StatusCode::BAD_REQUEST, "names needs at least one non-empty name per language code such as \"en\"".to_string()));
+    }
+    Ok(names)
+}
+
+async fn create_subject(State(pool): State<PgPool>, user: User, Json(input): Json<NewSubject>)
+    -> Result<(StatusCode, Json<Subject>), (StatusCode, String)>
+{
+    authorize(&user, Action::Create, &Resource::Subjects)?;
+    let code = subject_code(&input.code)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "code must consist of letters, digits and underscores".to_string()))?;
+    let names = subject_names(input.names)?;
+    let subject = sqlx::query_as::<_, Subject>(
+        "INSERT INTO subjects (code, names, active) VALUES ($1, $2, $3) RETURNING *"
+    )
+    .bind(code)
+    .bind(sqlx::types::Json(names))
+    .bind(input.active.unwrap_or(true))
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "A subject with this code already exists".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+    Ok((StatusCode::CREATED, Json(subject)))
+}
+
+#[derive(Deserialize)]
+struct SubjectQuery { active: Option<bool> }
+
+async fn list_subjects(State(pool): State<PgPool>, user: User, Query(query): Query<SubjectQuery>)
+    -> Result<Json<Vec<Subject>>, (StatusCode, String)>
+{
+    authorize(&user, Action::Read, &Resource::Subjects)?;
+    let subjects = sqlx::query_as::<_, Subject>(
+        "SELECT * FROM subjects WHERE ($1::bool IS NULL OR active = $1) ORDER BY code"
+    )
+    .bind(query.active)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(subjects))
+}
+
+async fn get_subject(State(pool): State<PgPool>, user: User, Path(subject_id): Path<Uuid>)
+    -> Result<Json<Subject>, (StatusCode, String)>
+{
+    authorize(&user, Action::Read, &Resource::Subjects)?;
+    sqlx::query_as::<_, Subject>("SELECT * FROM subjects WHERE id = $1")
+        .bind(subject_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Subject not found".to_string()))
+}
+
+#[derive(Deserialize)]
+struct UpdateSubject { code: Option<String>, names: Option<BTreeMap<String, String>>, active: Option<bool> }
+
+async fn update_subject(State(pool): State<PgPool>, user: User, Path(subject_id): Path<Uuid>, Json(input): Json<UpdateSubject>)
+    -> Result<Json<Subject>, (StatusCode, String)>
+{
+    authorize(&user, Action::Update, &Resource::Subjects)?;
+    let code = match &input.code {
+        Some(code) => Some(subject_code(code)
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, "code must consist of letters, digits and underscores".to_string()))?),
+        None => None,
+    };
+    // `names` replaces all translations at once
+    let names = input.names.map(subject_names).transpose()?.map(sqlx::types::Json);
+    sqlx::query_as::<_, Subject>(
+        r#"
+        UPDATE subjects
+        SET code = COALESCE($1, code), names = COALESCE($2, names), active = COALESCE($3, active)
+        WHERE id = $4
+        RETURNING *
+        "#
+    )
+    .bind(code)
+    .bind(names)
+    .bind(input.active)
+    .bind(subject_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "A subject with this code already exists".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?
+    .map(Json)
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "Subject not found".to_string()))
+}
+
+async fn delete_subject(State(pool): State<PgPool>, user: User, Path(subject_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    authorize(&user, Action::Delete, &Resource::Subjects)?;
+    let result = sqlx::query("DELETE FROM subjects WHERE id = $1")
+        .bind(subject_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                (StatusCode::CONFLICT, "Subject is in use; deactivate or merge it instead".to_string())
+            }
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+    if result.rows_affected() == 0 {
+        Err((StatusCode::NOT_FOUND, "Subject not found".into()))
+    } else {
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
+
+// Reconciles duplicates such as "Math" and "Mathematics": grades, curriculum
+// entries and teacher qualifications of the merged subjects move to this one,
+// which also takes over names in languages it has none for. The merged
+// subjects are deleted.
+async fn merge_subjects(State(pool): State<PgPool>, user: User, Path(subject_id): Path<Uuid>, Json(input): Json<MergeSubjects>)
+    -> Result<Json<MergedSubject>, (StatusCode, String)>
+{
+    authorize(&user, Action::Update, &Resource::Subjects)?;
+    let mut merged_ids = input.subject_ids;
+    merged_ids.sort();
+    merged_ids.dedup();
+    if merged_ids.is_empty() || merged_ids.contains(&subject_id) {
+        return Err((StatusCode::BAD_REQUEST, "subject_ids must list other subjects to merge into this one".to_string()));
+    }
 
-    Ok(Json(LoginResponse { user_id: user.id, role: user.role }))
-}
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
+    let locked = sqlx::query_scalar::<_, Uuid>("SELECT id FROM subjects WHERE id = $1 OR id = ANY($2) FOR UPDATE")
+        .bind(subject_id)
+        .bind(&merged_ids)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if locked.len() != merged_ids.len() + 1 {
+        return Err((StatusCode::NOT_FOUND, "Subject not found".to_string()));
+    }
 
+    let grades_moved = sqlx::query("UPDATE grades SET subject_id = $1 WHERE subject_id = ANY($2)")
+        .bind(subject_id)
+        .bind(&merged_ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .rows_affected();
 
+    // A class has a subject only once per term: where the merge would give it
+    // twice, one entry (this subject's own, if there is one) is kept and takes
+    // over the teachers of the others
+    let kept_entry = "(SELECT k.id FROM curriculum_entries k \
+         WHERE k.class_id = e.class_id AND k.term_id = e.term_id AND (k.subject_id = $1 OR k.subject_id = ANY($2)) \
+         ORDER BY k.subject_id = $1 DESC, k.created_at, k.id LIMIT 1)";
+    let merge_teachers = format!(
+        "INSERT INTO curriculum_teachers (entry_id, teacher_id) \
+         SELECT {kept_entry}, ct.teacher_id FROM curriculum_entries e JOIN curriculum_teachers ct ON ct.entry_id = e.id \
+         WHERE e.subject_id = ANY($2) ON CONFLICT DO NOTHING"
+    );
+    let drop_duplicates = format!("DELETE FROM curriculum_entries e WHERE e.subject_id = ANY($2) AND e.id <> {kept_entry}");
+    for sql in [merge_teachers, drop_duplicates] {
+        sqlx::query(&sql)
+            .bind(subject_id)
+            .bind(&merged_ids)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+    let curriculum_moved = sqlx::query("UPDATE curriculum_entries SET subject_id = $1 WHERE subject_id = ANY($2)")
+        .bind(subject_id)
+        .bind(&merged_ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .rows_affected();
 
-async fn create_user(State(pool): State<PgPool>, user: User, Json(input): Json<NewUser>)
-    -> Result<Json<User>, (StatusCode, String)>
-{
-    require_role(&user, &[Role::Admin])?;
+    sqlx::query(
+        r#"
+        INSERT INTO teacher_qualifications (teacher_id, subject_id)
+        SELECT teacher_id, $1 FROM teacher_qualifications WHERE subject_id = ANY($2)
+        ON CONFLICT DO NOTHING
+        "#
+    )
+    .bind(subject_id)
+    .bind(&merged_ids)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    sqlx::query("DELETE FROM teacher_qualifications WHERE subject_id = ANY($1)")
+        .bind(&merged_ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let row = sqlx::query_as::<_, User>(
+    let subject = sqlx::query_as::<_, Subject>(
         r#"
-        INSERT INTO users (email, hashed_password, role, first_name, last_name)
-        VALUES ($1, crypt($2, gen_salt('bf')), $3, $4, $5)
+        UPDATE subjects SET names = COALESCE(
+            (SELECT jsonb_object_agg(n.key, n.value) FROM subjects m, jsonb_each(m.names) n WHERE m.id = ANY($2)),
+            '{}'::jsonb
+        ) || names
+        WHERE id = $1
         RETURNING *
         "#
     )
-    .bind(&input.email)
-    .bind(&input.password)
-    .bind(&input.role)
-    .bind(&input.first_name)
-    .bind(&input.last_name)
-    .fetch_one(&pool)
+    .bind(subject_id)
+    .bind(&merged_ids)
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(row))
+    sqlx::query("DELETE FROM subjects WHERE id = ANY($1)")
+        .bind(&merged_ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    tracing::info!(%subject_id, merged = ?merged_ids, grades_moved, curriculum_moved, "subjects merged");
+    Ok(Json(MergedSubject { subject, grades_moved, curriculum_moved }))
 }
 
-async fn list_users(State(pool): State<PgPool>, user: User)
-    -> Result<Json<Vec<User>>, (StatusCode, String)>
+// === Teacher profiles: Admin/Director manage, teachers see and edit their own ===
+const TEACHER_SELECT: &str = r#"
+    SELECT u.id AS user_id, u.first_name, u.last_name, u.email, u.school_id,
+           p.phone, p.address, p.date_of_birth, p.hired_on,
+           ARRAY(
+               SELECT q.subject_id FROM teacher_qualifications q JOIN subjects sub ON sub.id = q.subject_id
+               WHERE q.teacher_id = u.id ORDER BY sub.code
+           ) AS subject_ids
+    FROM users u
+    LEFT JOIN teacher_profiles p ON p.user_id = u.id
+    WHERE 'teacher' = ANY(u.roles)
+"#;
+
+async fn fetch_teacher(pool: &PgPool, teacher_id: Uuid) -> Result<TeacherProfile, (StatusCode, String)> {
+    sqlx::query_as::<_, TeacherProfile>(&format!("{} AND u.id = $1", TEACHER_SELECT))
+        .bind(teacher_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Teacher not found".to_string()))
+}
+
+#[derive(Deserialize)]
+struct TeacherQuery { school_id: Option<Uuid>, subject_id: Option<Uuid> }
+
+async fn list_teachers(State(pool): State<PgPool>, user: User, Query(query): Query<TeacherQuery>)
+    -> Result<Json<Vec<TeacherProfile>>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director])?;
-    let users = sqlx::query_as::<_, User>("SELECT * FROM users")
+    authorize(&user, Action::Read, &Resource::Teachers)?;
+    // `subject_id` narrows the list down to the teachers qualified for it
+    let sql = format!(
+        r#"
+        {} AND ($2::uuid IS NULL OR u.school_id = $2)
+           AND ($3::uuid IS NULL OR EXISTS (SELECT 1 FROM teacher_qualifications q WHERE q.teacher_id = u.id AND q.subject_id = $3))
+           AND {}
+        ORDER BY u.last_name, u.first_name
+        "#,
+        TEACHER_SELECT,
+        tenant_condition(&user, "u.school_id")
+    );
+    let teachers = sqlx::query_as::<_, TeacherProfile>(&sql)
+        .bind(user.id)
+        .bind(query.school_id)
+        .bind(query.subject_id)
         .fetch_all(&pool)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    Ok(Json(users))
+    Ok(Json(teachers))
 }
 
-#[derive(Deserialize)]
-struct UpdateUser {
-    email: Option<String>,
-    password: Option<String>,
-    role: Option<Role>,
-    first_name: Option<String>,
-    last_name: Option<String>,
+async fn get_teacher(State(pool): State<PgPool>, user: User, Path(teacher_id): Path<Uuid>)
+    -> Result<Json<TeacherProfile>, (StatusCode, String)>
+{
+    let teacher = teacher_facts(&pool, teacher_id).await?;
+    authorize(&user, Action::Read, &Resource::Teacher(&teacher))?;
+    Ok(Json(fetch_teacher(&pool, teacher_id).await?))
 }
 
-async fn update_user(State(pool): State<PgPool>, user: User, Path(user_id): Path<Uuid>, Json(input): Json<UpdateUser>)
-    -> Result<Json<User>, (StatusCode, String)>
+async fn update_teacher(State(pool): State<PgPool>, user: User, Path(teacher_id): Path<Uuid>, Json(input): Json<UpdateTeacherProfile>)
+    -> Result<Json<TeacherProfile>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin])?;
-
-    let updated_user = sqlx::query_as::<_, User>(
+    let teacher = teacher_facts(&pool, teacher_id).await?;
+    authorize(&user, Action::Update, &Resource::Teacher(&teacher))?;
+    sqlx::query(
         r#"
-        UPDATE users
-        SET
-            email = COALESCE($1, email),
-            hashed_password = COALESCE(
-                CASE WHEN $2 IS NOT NULL THEN crypt($2, gen_salt('bf')) ELSE NULL END,
-                hashed_password
-            ),
-            role = COALESCE($3, role),
-            first_name = COALESCE($4, first_name),
-            last_name = COALESCE($5, last_name)
-        WHERE id = $6
-        RETURNING *
+        INSERT INTO teacher_profiles (user_id, phone, address, date_of_birth, hired_on)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id) DO UPDATE SET
+            phone = COALESCE($2, teacher_profiles.phone),
+            address = COALESCE($3, teacher_profiles.address),
+            date_of_birth = COALESCE($4, teacher_profiles.date_of_birth),
+            hired_on = COALESCE($5, teacher_profiles.hired_on),
+            updated_at = now()
         "#
     )
-    .bind(input.email)
-    .bind(input.password)
-    .bind(input.role)
-    .bind(input.first_name)
-    .bind(input.last_name)
-    .bind(user_id)
-    .fetch_one(&pool)
+    .bind(teacher_id)
+    .bind(input.phone.map(|phone| phone.trim().to_string()))
+    .bind(input.address)
+    .bind(input.date_of_birth)
+    .bind(input.hired_on)
+    .execute(&pool)
     .await
-    .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
-
-    Ok(Json(updated_user))
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(fetch_teacher(&pool, teacher_id).await?))
 }
 
-
-async fn delete_user(State(pool): State<PgPool>, user: User, Path(user_id): Path<Uuid>)
-    -> Result<StatusCode, (StatusCode, String)>
+async fn set_teacher_subjects(State(pool): State<PgPool>, user: User, Path(teacher_id): Path<Uuid>, Json(input): Json<TeacherQualifications>)
+    -> Result<Json<TeacherProfile>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin])?;
-    let result = sqlx::query!("DELETE FROM users WHERE id = $1", user_id)
-        .execute(&pool)
+    let teacher = teacher_facts(&pool, teacher_id).await?;
+    authorize(&user, Action::Update, &Resource::TeacherQualifications(&teacher))?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    sqlx::query("DELETE FROM teacher_qualifications WHERE teacher_id = $1")
+        .bind(teacher_id)
+        .execute(&mut *tx)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    if result.rows_affected() == 0 {
-        Err((StatusCode::NOT_FOUND, "User not found".into()))
-    } else {
-        Ok(StatusCode::NO_CONTENT)
-    }
+    sqlx::query(
+        "INSERT INTO teacher_qualifications (teacher_id, subject_id) SELECT $1, unnest($2::uuid[]) ON CONFLICT DO NOTHING"
+    )
+    .bind(teacher_id)
+    .bind(&input.subject_ids)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+            (StatusCode::BAD_REQUEST, "subject_ids must refer to subjects".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(fetch_teacher(&pool, teacher_id).await?))
 }
 
 // === RBAC: ADMIN + Director ===
 async fn create_student(State(pool): State<PgPool>, user: User, Json(input): Json<NewStudent>)
     -> Result<Json<Student>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director])?;
-    let student = sqlx::query_as::<_, Student>(
-        r#"INSERT INTO students (user_id, class) VALUES ($1, $2) RETURNING *"#)
+    authorize(&user, Action::Create, &Resource::Students)?;
+    let account_school_id = school_of_user(&pool, input.user_id).await?;
+    authorize_school(&user, account_school_id)?;
+    let class_school_id = match input.class_id {
+        Some(class_id) => Some(class_facts(&pool, &user, class_id).await?.school_id),
+        None => None,
+    };
+    // Without an explicit school the student joins the school of their class or account
+    let school_id = input.school_id.or(class_school_id).or(account_school_id);
+    authorize_school(&user, school_id)?;
+    if class_school_id.is_some_and(|class_school_id| Some(class_school_id) != school_id) {
+        return Err((StatusCode::BAD_REQUEST, "The class belongs to another school".to_string()));
+    }
+
+    let student_id = sqlx::query_scalar::<_, Uuid>(
+        r#"INSERT INTO students (user_id, class_id, school_id) VALUES ($1, $2, $3) RETURNING id"#)
         .bind(input.user_id)
-        .bind(input.class)
+        .bind(input.class_id)
+        .bind(school_id)
         .fetch_one(&pool)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    Ok(Json(student))
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                (StatusCode::BAD_REQUEST, "school_id must refer to a school".to_string())
+            }
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+    Ok(Json(fetch_student(&pool, student_id).await?))
 }
 
 async fn delete_student(State(pool): State<PgPool>, user: User, Path(student_id): Path<Uuid>)
     -> Result<StatusCode, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director])?;
+    let student = student_facts(&pool, &user, student_id).await?;
+    authorize(&user, Action::Delete, &Resource::Student(&student))?;
     let result = sqlx::query!("DELETE FROM students WHERE id = $1", student_id)
         .execute(&pool)
         .await
@@ -230,98 +2685,170 @@ async fn delete_student(State(pool): State<PgPool>, user: User, Path(student_id)
     }
 }
 
-async fn list_students(State(pool): State<PgPool>, user: User)
+// Students with their class name and account details; followed by a WHERE clause.
+const STUDENT_SELECT: &str = r#"
+    SELECT s.id, s.user_id, s.class_id, s.school_id, s.created_at,
+           c.grade_level::text || c.letter AS class,
+           u.first_name, u.last_name, u.email
+    FROM students s
+    JOIN users u ON s.user_id = u.id
+    LEFT JOIN classes c ON c.id = s.class_id
+"#;
+
+async fn fetch_student(pool: &PgPool, student_id: Uuid) -> Result<Student, (StatusCode, String)> {
+    sqlx::query_as::<_, Student>(&format!("{} WHERE s.id = $1", STUDENT_SELECT))
+        .bind(student_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Student not found".to_string()))
+}
+
+async fn list_students(State(pool): State<PgPool>, user: User, Query(filter): Query<SchoolFilter>)
     -> Result<Json<Vec<Student>>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director, Role::Teacher, Role::Parent, Role::Student])?;
-    let students = sqlx::query_as::<_, Student>(
-        r#"
-        SELECT s.id, s.user_id, s.class, s.created_at,
-               u.first_name, u.last_name, u.email
-        FROM students s
-        JOIN users u ON s.user_id = u.id
-        "#
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    authorize(&user, Action::Read, &Resource::Students)?;
+
+    let mut sql = format!("{} WHERE ($2::uuid IS NULL OR s.school_id = $2)", STUDENT_SELECT);
+    if let Some(scope) = student_scope(&user) {
+        sql.push_str(" AND ");
+        sql.push_str(&scope);
+    }
+    sql.push_str(" ORDER BY c.grade_level, c.letter, u.last_name, u.first_name");
+
+    let students = sqlx::query_as::<_, Student>(&sql)
+        .bind(user.id)
+        .bind(filter.school_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     Ok(Json(students))
 }
 
 async fn get_student(State(pool): State<PgPool>, user: User, Path(student_id): Path<Uuid>)
     -> Result<Json<Student>, (StatusCode, String)>
 {
-    // Directors, Teachers (for their students), Parents (for their children), Students (for self)
-    // Simplified: allow all, restrict in prod!
-    require_role(&user, &[Role::Admin, Role::Director, Role::Teacher, Role::Parent, Role::Student])?;
-    let student = sqlx::query_as::<_, Student>(
-        r#"
-        SELECT s.id, s.user_id, s.class, s.created_at,
-               u.first_name, u.last_name, u.email
-        FROM students s
-        JOIN users u ON s.user_id = u.id
-        WHERE s.id = $1
-        "#
-    )
-    .bind(student_id)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
-    Ok(Json(student))
+    let facts = student_facts(&pool, &user, student_id).await?;
+    authorize(&user, Action::Read, &Resource::Student(&facts))?;
+    Ok(Json(fetch_student(&pool, student_id).await?))
 }
 
 #[derive(Deserialize)]
-struct UpdateStudent { class: Option<String> }
+struct UpdateStudent { class_id: Option<Uuid>, school_id: Option<Uuid> }
 
 async fn update_student(State(pool): State<PgPool>, user: User, Path(student_id): Path<Uuid>, Json(input): Json<UpdateStudent>)
     -> Result<Json<Student>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director])?;
-    let updated_student = sqlx::query_as::<_, Student>(
-        r#"UPDATE students SET class = COALESCE($1, class) WHERE id = $2 RETURNING *"#)
-        .bind(input.class)
+    let student = student_facts(&pool, &user, student_id).await?;
+    authorize(&user, Action::Update, &Resource::Student(&student))?;
+    let school_id = input.school_id.or(student.school_id);
+    authorize_school(&user, school_id)?;
+    if let Some(class_id) = input.class_id {
+        if Some(class_facts(&pool, &user, class_id).await?.school_id) != school_id {
+            return Err((StatusCode::BAD_REQUEST, "The class belongs to another school".to_string()));
+        }
+    }
+
+    // A student moved to another school without a new class leaves their old one
+    sqlx::query(
+        r#"
+        UPDATE students SET
+            class_id = CASE
+                WHEN $1::uuid IS NOT NULL THEN $1
+                WHEN school_id IS DISTINCT FROM $2 THEN NULL
+                ELSE class_id
+            END,
+            school_id = $2
+        WHERE id = $3
+        "#)
+        .bind(input.class_id)
+        .bind(school_id)
         .bind(student_id)
-        .fetch_one(&pool)
+        .execute(&pool)
         .await
-        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
-    Ok(Json(updated_student))
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                (StatusCode::BAD_REQUEST, "school_id must refer to a school".to_string())
+            }
+            e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+    Ok(Json(fetch_student(&pool, student_id).await?))
 }
 
 // === GRADES: Teachers (for their students), Admin, Director ===
 async fn create_grade(State(pool): State<PgPool>, user: User, Json(input): Json<NewGrade>)
     -> Result<Json<Grade>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director, Role::Teacher])?;
-    // TODO: Further restrict teachers to their students
+    // Grades belong to the term of the day they are given
+    let term_id = term_on(&pool, input.student_id, chrono::Utc::now().date_naive()).await?;
+    let grade = grade_facts_for(&pool, &user, input.student_id, input.subject_id, term_id).await?;
+    authorize(&user, Action::Create, &Resource::Grade(&grade))?;
+    check_subject_active(&pool, input.subject_id).await?;
+
+    // The author is the teacher making the request; staff entering a grade on
+    // someone's behalf have to name an actual teacher and are recorded as `entered_by`.
+    let (teacher_id, entered_by) = match input.teacher_id {
+        Some(teacher_id) if teacher_id != user.id || !user.has_role(Role::Teacher) => {
+            if !user.has_any_role(&[Role::Admin, Role::Director]) {
+                return Err((StatusCode::FORBIDDEN, "Teachers can only record grades in their own name".into()));
+            }
+            if !is_teacher(&pool, teacher_id).await? {
+                return Err((StatusCode::BAD_REQUEST, "teacher_id must refer to a teacher".into()));
+            }
+            authorize_school(&user, school_of_user(&pool, teacher_id).await?)?;
+            (teacher_id, Some(user.id))
+        }
+        _ if user.has_role(Role::Teacher) => (user.id, None),
+        _ => return Err((StatusCode::BAD_REQUEST, "teacher_id is required when entering a grade for a teacher".into())),
+    };
+    check_teacher_qualified(&pool, teacher_id, input.subject_id).await?;
+
     let grade = sqlx::query_as::<_, Grade>(
-        r#"INSERT INTO grades (student_id, subject, value, teacher_id) VALUES ($1, $2, $3, $4) RETURNING *"#)
+        r#"INSERT INTO grades (student_id, subject_id, value, teacher_id, entered_by, term_id) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *"#)
         .bind(input.student_id)
-        .bind(&input.subject)
+        .bind(input.subject_id)
         .bind(input.value)
-        .bind(input.teacher_id)
+        .bind(teacher_id)
+        .bind(entered_by)
+        .bind(term_id)
         .fetch_one(&pool)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if let Some(entered_by) = entered_by {
+        tracing::info!(grade_id = %grade.id, %teacher_id, %entered_by, "grade entered on a teacher's behalf");
+    }
     Ok(Json(grade))
 }
 
 #[derive(Deserialize)]
-struct UpdateGrade { value: Option<i16>, subject: Option<String> }
+struct UpdateGrade { value: Option<i16>, subject_id: Option<Uuid> }
 
 async fn update_grade(State(pool): State<PgPool>, user: User, Path(grade_id): Path<Uuid>, Json(input): Json<UpdateGrade>)
     -> Result<Json<Grade>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director, Role::Teacher])?;
-    // TODO: Restrict teachers to grades they own
+    let (grade, student_id, term_id) = grade_facts(&pool, &user, grade_id).await?;
+    authorize(&user, Action::Update, &Resource::Grade(&grade))?;
+    // Moving a grade to another subject needs the right to grade that subject too
+    if let Some(subject_id) = input.subject_id {
+        let moved = grade_facts_for(&pool, &user, student_id, subject_id, term_id).await?;
+        authorize(&user, Action::Update, &Resource::Grade(&moved))?;
+        check_subject_active(&pool, subject_id).await?;
+        let teacher_id = sqlx::query_scalar::<_, Uuid>("SELECT teacher_id FROM grades WHERE id = $1")
+            .bind(grade_id)
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        check_teacher_qualified(&pool, teacher_id, subject_id).await?;
+    }
     let updated_grade = sqlx::query_as::<_, Grade>(
         r#"
         UPDATE grades
-        SET value = COALESCE($1, value), subject = COALESCE($2, subject)
+        SET value = COALESCE($1, value), subject_id = COALESCE($2, subject_id)
         WHERE id = $3
         RETURNING *
         "#)
         .bind(input.value)
-        .bind(input.subject)
+        .bind(input.subject_id)
         .bind(grade_id)
         .fetch_one(&pool)
         .await
@@ -332,8 +2859,8 @@ async fn update_grade(State(pool): State<PgPool>, user: User, Path(grade_id): Pa
 async fn delete_grade(State(pool): State<PgPool>, user: User, Path(grade_id): Path<Uuid>)
     -> Result<StatusCode, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director, Role::Teacher])?;
-    // TODO: Restrict teachers to grades they own
+    let (grade, _, _) = grade_facts(&pool, &user, grade_id).await?;
+    authorize(&user, Action::Delete, &Resource::Grade(&grade))?;
     let result = sqlx::query!("DELETE FROM grades WHERE id = $1", grade_id)
         .execute(&pool)
         .await
@@ -345,75 +2872,27 @@ async fn delete_grade(State(pool): State<PgPool>, user: User, Path(grade_id): Pa
     }
 }
 
-async fn list_grades(State(pool): State<PgPool>, user: User)
+async fn list_grades(State(pool): State<PgPool>, user: User, Query(filter): Query<RecordFilter>)
     -> Result<Json<Vec<Grade>>, (StatusCode, String)>
 {
-    use Role::*;
-
-    match user.role {
-        Admin | Director | Teacher => {
-            // Full access: return all grades
-            let grades = sqlx::query_as::<_, Grade>("SELECT * FROM grades")
-                .fetch_all(&pool)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-            Ok(Json(grades))
-        }
-        Parent => {
-            // Only grades for this parent's children
-            let student_ids = sqlx::query!(
-                "SELECT student_id FROM parent_students WHERE parent_id = $1",
-                user.id
-            )
-            .fetch_all(&pool)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-            // If parent has no children, return empty
-            if student_ids.is_empty() {
-                return Ok(Json(vec![]));
-            }
-
-            // Collect UUIDs of children
-            let ids: Vec<Uuid> = student_ids.into_iter().map(|rec| rec.student_id).collect();
-
-            // Use SQL's ANY for matching student_id in the list
-            let grades = sqlx::query_as::<_, Grade>(
-                "SELECT * FROM grades WHERE student_id = ANY($1)"
-            )
-            .bind(&ids)
-            .fetch_all(&pool)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-            Ok(Json(grades))
-        }
-        Student => {
-            // Only grades for this student
-            // First, find the student_id for this user
-            let rec = sqlx::query!(
-                "SELECT id FROM students WHERE user_id = $1",
-                user.id
-            )
-            .fetch_optional(&pool)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    authorize(&user, Action::Read, &Resource::Grades)?;
 
-            if let Some(student) = rec {
-                let grades = sqlx::query_as::<_, Grade>(
-                    "SELECT * FROM grades WHERE student_id = $1"
-                )
-                .bind(student.id)
-                .fetch_all(&pool)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-                Ok(Json(grades))
-            } else {
-                // This user is not linked to any student record
-                Ok(Json(vec![]))
-            }
-        }
-    }
+    // Grades of the students the caller can see, as for a single grade (all roles
+    // of the user count)
+    let scope = student_scope(&user).unwrap_or_else(|| "TRUE".to_string());
+    let sql = format!(
+        "SELECT * FROM grades WHERE student_id IN (SELECT s.id FROM students s WHERE ($2::uuid IS NULL OR s.school_id = $2) AND {}) AND {}",
+        scope, TERM_FILTER
+    );
+    let grades = sqlx::query_as::<_, Grade>(&sql)
+        .bind(user.id)
+        .bind(filter.school_id)
+        .bind(filter.term_id)
+        .bind(filter.academic_year_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(grades))
 }
 
 
@@ -421,7 +2900,9 @@ async fn list_grades(State(pool): State<PgPool>, user: User)
 async fn link_parent_student(State(pool): State<PgPool>, user: User, Json(input): Json<LinkParentStudent>)
     -> Result<Json<ParentStudent>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director])?;
+    authorize(&user, Action::Create, &Resource::ParentLinks)?;
+    authorize_school(&user, student_facts(&pool, &user, input.student_id).await?.school_id)?;
+    authorize_school(&user, school_of_user(&pool, input.parent_id).await?)?;
     let record = sqlx::query_as!(
         ParentStudent,
         r#"INSERT INTO parent_students (parent_id, student_id) VALUES ($1, $2) RETURNING parent_id, student_id"#,
@@ -437,12 +2918,10 @@ async fn link_parent_student(State(pool): State<PgPool>, user: User, Json(input)
 async fn students_for_parent(State(pool): State<PgPool>, user: User, Path(parent_id): Path<Uuid>)
     -> Result<Json<Vec<ParentStudent>>, (StatusCode, String)>
 {
-    if user.role != Role::Parent && user.role != Role::Admin && user.role != Role::Director {
-        return Err((StatusCode::FORBIDDEN, "Insufficient permissions".to_string()));
-    }
     // Parents can only access their own record
-    if user.role == Role::Parent && parent_id != user.id {
-        return Err((StatusCode::FORBIDDEN, "Parents can only access their own children".to_string()));
+    authorize(&user, Action::Read, &Resource::ParentLinksOf(parent_id))?;
+    if parent_id != user.id {
+        authorize_school(&user, school_of_user(&pool, parent_id).await?)?;
     }
     let result = sqlx::query_as!(
         ParentStudent,
@@ -458,7 +2937,8 @@ async fn students_for_parent(State(pool): State<PgPool>, user: User, Path(parent
 async fn delete_parent_student(State(pool): State<PgPool>, user: User, Path((parent_id, student_id)): Path<(Uuid, Uuid)>)
     -> Result<StatusCode, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director])?;
+    authorize(&user, Action::Delete, &Resource::ParentLinks)?;
+    authorize_school(&user, student_facts(&pool, &user, student_id).await?.school_id)?;
     let result = sqlx::query!(
         "DELETE FROM parent_students WHERE parent_id = $1 AND student_id = $2",
         parent_id, student_id
@@ -473,86 +2953,328 @@ async fn delete_parent_student(State(pool): State<PgPool>, user: User, Path((par
     }
 }
 
-// === Absence handlers: Teachers/Admin/Director ===
-async fn create_absence(State(pool): State<PgPool>, user: User, Json(input): Json<NewAbsence>)
-    -> Result<Json<Absence>, (StatusCode, String)>
+// === Curriculum: Admin/Director manage, everyone sees their school's ===
+const CURRICULUM_SELECT: &str = r#"
+    SELECT ce.id, ce.class_id, ce.term_id, ce.subject_id, ce.weekly_hours, ce.created_at,
+           ARRAY(SELECT ct.teacher_id FROM curriculum_teachers ct WHERE ct.entry_id = ce.id ORDER BY ct.teacher_id) AS teacher_ids
+    FROM curriculum_entries ce
+    JOIN classes c ON c.id = ce.class_id
+    JOIN subjects sub ON sub.id = ce.subject_id
+"#;
+
+async fn fetch_curriculum_entry(pool: &PgPool, entry_id: Uuid) -> Result<CurriculumEntry, (StatusCode, String)> {
+    sqlx::query_as::<_, CurriculumEntry>(&format!("{} WHERE ce.id = $1", CURRICULUM_SELECT))
+        .bind(entry_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Curriculum entry not found".to_string()))
+}
+
+// A class only has curriculum in the terms of its own academic year.
+async fn check_curriculum_term(pool: &PgPool, class_id: Uuid, term_id: Uuid) -> Result<(), (StatusCode, String)> {
+    let in_class_year = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT t.academic_year_id = c.academic_year_id
+        FROM terms t, classes c
+        WHERE t.id = $1 AND c.id = $2
+        "#
+    )
+    .bind(term_id)
+    .bind(class_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or_else(|| (StatusCode::BAD_REQUEST, "term_id must refer to a term".to_string()))?;
+    if in_class_year {
+        Ok(())
+    } else {
+        Err((StatusCode::BAD_REQUEST, "The term is not in the class's academic year".to_string()))
+    }
+}
+
+// Whoever teaches a subject to a class is a teacher of the class's school and
+// qualified for the subject. `teacher_ids` holds no duplicates.
+async fn check_curriculum_teachers(pool: &PgPool, teacher_ids: &[Uuid], school_id: Uuid, subject_id: Uuid)
+    -> Result<(), (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director, Role::Teacher])?;
-    let absence = sqlx::query_as::<_, Absence>(
-        r#"INSERT INTO absences (student_id, date, reason) VALUES ($1, $2, $3) RETURNING *"#)
-        .bind(input.student_id)
-        .bind(input.date)
-        .bind(input.reason)
-        .fetch_one(&pool)
+    let (teachers, qualified) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT
+            count(*) FILTER (WHERE 'teacher' = ANY(u.roles) AND u.school_id = $2),
+            count(*) FILTER (WHERE EXISTS (SELECT 1 FROM teacher_qualifications q WHERE q.teacher_id = u.id AND q.subject_id = $3))
+        FROM users u WHERE u.id = ANY($1)
+        "#
+    )
+    .bind(teacher_ids)
+    .bind(school_id)
+    .bind(subject_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if teachers != teacher_ids.len() as i64 {
+        return Err((StatusCode::BAD_REQUEST, "teacher_ids must refer to teachers of the class's school".to_string()));
+    }
+    if qualified != teacher_ids.len() as i64 {
+        return Err((StatusCode::BAD_REQUEST, "Every teacher has to be qualified to teach the subject".to_string()));
+    }
+    Ok(())
+}
+
+async fn set_curriculum_teachers(conn: &mut sqlx::PgConnection, entry_id: Uuid, teacher_ids: &[Uuid]) -> Result<(), (StatusCode, String)> {
+    sqlx::query("DELETE FROM curriculum_teachers WHERE entry_id = $1")
+        .bind(entry_id)
+        .execute(&mut *conn)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    Ok(Json(absence))
+    sqlx::query("INSERT INTO curriculum_teachers (entry_id, teacher_id) SELECT $1, unnest($2::uuid[])")
+        .bind(entry_id)
+        .bind(teacher_ids)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(())
 }
 
-async fn list_absences(State(pool): State<PgPool>, user: User)
-    -> Result<Json<Vec<Absence>>, (StatusCode, String)>
+async fn create_curriculum_entry(State(pool): State<PgPool>, user: User, Json(input): Json<NewCurriculumEntry>)
+    -> Result<(StatusCode, Json<CurriculumEntry>), (StatusCode, String)>
 {
-    use Role::*;
+    let class = class_facts(&pool, &user, input.class_id).await?;
+    authorize(&user, Action::Create, &Resource::ClassCurriculum(&class))?;
+    if !(1..=40).contains(&input.weekly_hours) {
+        return Err((StatusCode::BAD_REQUEST, "weekly_hours must be between 1 and 40".to_string()));
+    }
+    check_subject_active(&pool, input.subject_id).await?;
+    check_curriculum_term(&pool, input.class_id, input.term_id).await?;
+    let mut teacher_ids = input.teacher_ids;
+    teacher_ids.sort();
+    teacher_ids.dedup();
+    check_curriculum_teachers(&pool, &teacher_ids, class.school_id, input.subject_id).await?;
 
-    match user.role {
-        Admin | Director | Teacher => {
-            // See all absences
-            let absences = sqlx::query_as::<_, Absence>("SELECT * FROM absences")
-                .fetch_all(&pool)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-            Ok(Json(absences))
-        }
-        Parent => {
-            // Only absences for this parent's children
-            let student_ids = sqlx::query!(
-                "SELECT student_id FROM parent_students WHERE parent_id = $1",
-                user.id
-            )
-            .fetch_all(&pool)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let entry_id = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO curriculum_entries (class_id, term_id, subject_id, weekly_hours) VALUES ($1, $2, $3, $4) RETURNING id"
+    )
+    .bind(input.class_id)
+    .bind(input.term_id)
+    .bind(input.subject_id)
+    .bind(input.weekly_hours)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, "The class already has this subject in that term".to_string())
+        }
+        e => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    })?;
+    set_curriculum_teachers(&mut tx, entry_id, &teacher_ids).await?;
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-            if student_ids.is_empty() {
-                return Ok(Json(vec![]));
-            }
+    Ok((StatusCode::CREATED, Json(fetch_curriculum_entry(&pool, entry_id).await?)))
+}
 
-            let ids: Vec<Uuid> = student_ids.into_iter().map(|rec| rec.student_id).collect();
+#[derive(Deserialize)]
+struct CurriculumQuery { class_id: Option<Uuid>, term_id: Option<Uuid>, teacher_id: Option<Uuid>, school_id: Option<Uuid> }
 
-            let absences = sqlx::query_as::<_, Absence>(
-                "SELECT * FROM absences WHERE student_id = ANY($1)"
-            )
-            .bind(&ids)
-            .fetch_all(&pool)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+async fn list_curriculum(State(pool): State<PgPool>, user: User, Query(query): Query<CurriculumQuery>)
+    -> Result<Json<Vec<CurriculumEntry>>, (StatusCode, String)>
+{
+    authorize(&user, Action::Read, &Resource::Curriculum)?;
+    let sql = format!(
+        r#"
+        {} WHERE ($2::uuid IS NULL OR ce.class_id = $2) AND ($3::uuid IS NULL OR ce.term_id = $3)
+           AND ($4::uuid IS NULL OR EXISTS (SELECT 1 FROM curriculum_teachers ct WHERE ct.entry_id = ce.id AND ct.teacher_id = $4))
+           AND ($5::uuid IS NULL OR c.school_id = $5) AND {}
+        ORDER BY c.grade_level, c.letter, sub.code
+        "#,
+        CURRICULUM_SELECT,
+        tenant_condition(&user, "c.school_id")
+    );
+    let entries = sqlx::query_as::<_, CurriculumEntry>(&sql)
+        .bind(user.id)
+        .bind(query.class_id)
+        .bind(query.term_id)
+        .bind(query.teacher_id)
+        .bind(query.school_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(entries))
+}
+
+async fn get_curriculum_entry(State(pool): State<PgPool>, user: User, Path(entry_id): Path<Uuid>)
+    -> Result<Json<CurriculumEntry>, (StatusCode, String)>
+{
+    let entry = fetch_curriculum_entry(&pool, entry_id).await?;
+    let class = class_facts(&pool, &user, entry.class_id).await?;
+    authorize(&user, Action::Read, &Resource::ClassCurriculum(&class))?;
+    Ok(Json(entry))
+}
+
+#[derive(Deserialize)]
+struct UpdateCurriculumEntry { weekly_hours: Option<i16>, teacher_ids: Option<Vec<Uuid>> }
+
+async fn update_curriculum_entry(State(pool): State<PgPool>, user: User, Path(entry_id): Path<Uuid>, Json(input): Json<UpdateCurriculumEntry>)
+    -> Result<Json<CurriculumEntry>, (StatusCode, String)>
+{
+    let entry = fetch_curriculum_entry(&pool, entry_id).await?;
+    let class = class_facts(&pool, &user, entry.class_id).await?;
+    authorize(&user, Action::Update, &Resource::ClassCurriculum(&class))?;
+    if input.weekly_hours.is_some_and(|hours| !(1..=40).contains(&hours)) {
+        return Err((StatusCode::BAD_REQUEST, "weekly_hours must be between 1 and 40".to_string()));
+    }
+    // `teacher_ids` replaces all teachers of the entry
+    let teacher_ids = input.teacher_ids.map(|mut teacher_ids| {
+        teacher_ids.sort();
+        teacher_ids.dedup();
+        teacher_ids
+    });
+    if let Some(teacher_ids) = &teacher_ids {
+        check_curriculum_teachers(&pool, teacher_ids, class.school_id, entry.subject_id).await?;
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    sqlx::query("UPDATE curriculum_entries SET weekly_hours = COALESCE($1, weekly_hours) WHERE id = $2")
+        .bind(input.weekly_hours)
+        .bind(entry_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if let Some(teacher_ids) = &teacher_ids {
+        set_curriculum_teachers(&mut tx, entry_id, teacher_ids).await?;
+    }
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(fetch_curriculum_entry(&pool, entry_id).await?))
+}
+
+async fn delete_curriculum_entry(State(pool): State<PgPool>, user: User, Path(entry_id): Path<Uuid>)
+    -> Result<StatusCode, (StatusCode, String)>
+{
+    let entry = fetch_curriculum_entry(&pool, entry_id).await?;
+    let class = class_facts(&pool, &user, entry.class_id).await?;
+    authorize(&user, Action::Delete, &Resource::ClassCurriculum(&class))?;
+    sqlx::query("DELETE FROM curriculum_entries WHERE id = $1")
+        .bind(entry_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Starts a term's curriculum from the previous one. Subjects a class already has
+// in the new term are left as they are, as are retired subjects; teachers who
+// no longer teach at the school or lost the qualification are not carried over.
+async fn copy_curriculum(State(pool): State<PgPool>, user: User, Json(input): Json<CopyCurriculum>)
+    -> Result<(StatusCode, Json<Vec<CurriculumEntry>>), (StatusCode, String)>
+{
+    authorize(&user, Action::Create, &Resource::Curriculum)?;
+    if input.from_term_id == input.to_term_id {
+        return Err((StatusCode::BAD_REQUEST, "from_term_id and to_term_id must be different terms".to_string()));
+    }
+    let years = sqlx::query_as::<_, (Uuid, Uuid)>(
+        "SELECT y.id, y.school_id FROM terms t JOIN academic_years y ON y.id = t.academic_year_id WHERE t.id = ANY($1)"
+    )
+    .bind([input.from_term_id, input.to_term_id])
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let [(first_year, school_id), (second_year, _)] = years[..] else {
+        return Err((StatusCode::NOT_FOUND, "Term not found".to_string()));
+    };
+    authorize_school(&user, Some(school_id))?;
+    // Classes belong to one academic year, so their curriculum can't move to another
+    if first_year != second_year {
+        return Err((StatusCode::BAD_REQUEST, "Curriculum can only be copied between terms of the same academic year".to_string()));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let entry_ids = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO curriculum_entries (class_id, term_id, subject_id, weekly_hours)
+        SELECT ce.class_id, $2, ce.subject_id, ce.weekly_hours
+        FROM curriculum_entries ce JOIN subjects sub ON sub.id = ce.subject_id
+        WHERE ce.term_id = $1 AND ($3::uuid IS NULL OR ce.class_id = $3) AND sub.active
+        ON CONFLICT (class_id, term_id, subject_id) DO NOTHING
+        RETURNING id
+        "#
+    )
+    .bind(input.from_term_id)
+    .bind(input.to_term_id)
+    .bind(input.class_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    sqlx::query(
+        r#"
+        INSERT INTO curriculum_teachers (entry_id, teacher_id)
+        SELECT copy.id, ct.teacher_id
+        FROM curriculum_entries copy
+        JOIN curriculum_entries ce ON ce.class_id = copy.class_id AND ce.subject_id = copy.subject_id AND ce.term_id = $2
+        JOIN curriculum_teachers ct ON ct.entry_id = ce.id
+        JOIN users u ON u.id = ct.teacher_id
+        WHERE copy.id = ANY($1) AND 'teacher' = ANY(u.roles) AND u.school_id = $3
+          AND EXISTS (SELECT 1 FROM teacher_qualifications q WHERE q.teacher_id = u.id AND q.subject_id = copy.subject_id)
+        "#
+    )
+    .bind(&entry_ids)
+    .bind(input.from_term_id)
+    .bind(school_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let entries = sqlx::query_as::<_, CurriculumEntry>(
+        &format!("{} WHERE ce.id = ANY($1) ORDER BY c.grade_level, c.letter, sub.code", CURRICULUM_SELECT)
+    )
+    .bind(&entry_ids)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok((StatusCode::CREATED, Json(entries)))
+}
+
+// === Absence handlers: Teachers/Admin/Director ===
+async fn create_absence(State(pool): State<PgPool>, user: User, Json(input): Json<NewAbsence>)
+    -> Result<Json<Absence>, (StatusCode, String)>
+{
+    let term_id = term_on(&pool, input.student_id, input.date).await?;
+    let absence = absence_facts_for(&pool, &user, input.student_id, term_id).await?;
+    authorize(&user, Action::Create, &Resource::Absence(&absence))?;
+    let absence = sqlx::query_as::<_, Absence>(
+        r#"INSERT INTO absences (student_id, date, reason, term_id) VALUES ($1, $2, $3, $4) RETURNING *"#)
+        .bind(input.student_id)
+        .bind(input.date)
+        .bind(input.reason)
+        .bind(term_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(absence))
+}
 
-            Ok(Json(absences))
-        }
-        Student => {
-            // Only absences for this student
-            let rec = sqlx::query!(
-                "SELECT id FROM students WHERE user_id = $1",
-                user.id
-            )
-            .fetch_optional(&pool)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+async fn list_absences(State(pool): State<PgPool>, user: User, Query(filter): Query<RecordFilter>)
+    -> Result<Json<Vec<Absence>>, (StatusCode, String)>
+{
+    authorize(&user, Action::Read, &Resource::Absences)?;
 
-            if let Some(student) = rec {
-                let absences = sqlx::query_as::<_, Absence>(
-                    "SELECT * FROM absences WHERE student_id = $1"
-                )
-                .bind(student.id)
-                .fetch_all(&pool)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-                Ok(Json(absences))
-            } else {
-                Ok(Json(vec![]))
-            }
-        }
-    }
+    // Absences of the students the caller can see, as for a single absence (all
+    // roles of the user count)
+    let scope = student_scope(&user).unwrap_or_else(|| "TRUE".to_string());
+    let sql = format!(
+        "SELECT * FROM absences WHERE student_id IN (SELECT s.id FROM students s WHERE ($2::uuid IS NULL OR s.school_id = $2) AND {}) AND {}",
+        scope, TERM_FILTER
+    );
+    let absences = sqlx::query_as::<_, Absence>(&sql)
+        .bind(user.id)
+        .bind(filter.school_id)
+        .bind(filter.term_id)
+        .bind(filter.academic_year_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(absences))
 }
 
 
@@ -562,11 +3284,28 @@ struct UpdateAbsence { date: Option<chrono::NaiveDate>, reason: Option<String> }
 async fn update_absence(State(pool): State<PgPool>, user: User, Path(absence_id): Path<Uuid>, Json(input): Json<UpdateAbsence>)
     -> Result<Json<Absence>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director, Role::Teacher])?;
+    let (absence, student_id) = absence_facts(&pool, &user, absence_id).await?;
+    authorize(&user, Action::Update, &Resource::Absence(&absence))?;
+    // A new date may fall in another term, which needs the right to record absences there too
+    let term_id = match input.date {
+        Some(date) => {
+            let term_id = term_on(&pool, student_id, date).await?;
+            let moved = absence_facts_for(&pool, &user, student_id, term_id).await?;
+            authorize(&user, Action::Update, &Resource::Absence(&moved))?;
+            term_id
+        }
+        None => None,
+    };
     let updated_absence = sqlx::query_as::<_, Absence>(
-        r#"UPDATE absences SET date = COALESCE($1, date), reason = COALESCE($2, reason) WHERE id = $3 RETURNING *"#)
+        r#"
+        UPDATE absences
+        SET date = COALESCE($1, date), reason = COALESCE($2, reason), term_id = CASE WHEN $1::date IS NULL THEN term_id ELSE $3 END
+        WHERE id = $4
+        RETURNING *
+        "#)
         .bind(input.date)
         .bind(input.reason)
+        .bind(term_id)
         .bind(absence_id)
         .fetch_one(&pool)
         .await
@@ -577,7 +3316,8 @@ async fn update_absence(State(pool): State<PgPool>, user: User, Path(absence_id)
 async fn delete_absence(State(pool): State<PgPool>, user: User, Path(absence_id): Path<Uuid>)
     -> Result<StatusCode, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director, Role::Teacher])?;
+    let (absence, _) = absence_facts(&pool, &user, absence_id).await?;
+    authorize(&user, Action::Delete, &Resource::Absence(&absence))?;
     let result = sqlx::query!("DELETE FROM absences WHERE id = $1", absence_id)
         .execute(&pool)
         .await
@@ -590,34 +3330,45 @@ async fn delete_absence(State(pool): State<PgPool>, user: User, Path(absence_id)
 }
 
 // === Statistics: Directors/Admin only ===
-async fn stats_avg_grade(State(pool): State<PgPool>, user: User)
+async fn stats_avg_grade(State(pool): State<PgPool>, user: User, Query(filter): Query<RecordFilter>)
     -> Result<Json<Vec<StudentAvgGrade>>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director, Role::Teacher, Role::Parent, Role::Student])?;
-    let rows = sqlx::query_as!(
-        StudentAvgGrade,
-        r#"SELECT student_id, AVG(value)::float8 as avg_grade FROM grades GROUP BY student_id"#
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    authorize(&user, Action::Read, &Resource::Stats)?;
+    let scope = student_scope(&user).unwrap_or_else(|| "TRUE".to_string());
+    let sql = format!(
+        "SELECT student_id, AVG(value)::float8 AS avg_grade FROM grades WHERE student_id IN (SELECT s.id FROM students s WHERE ($2::uuid IS NULL OR s.school_id = $2) AND {}) AND {} GROUP BY student_id",
+        scope, TERM_FILTER
+    );
+
+    let rows = sqlx::query_as::<_, StudentAvgGrade>(&sql)
+        .bind(user.id)
+        .bind(filter.school_id)
+        .bind(filter.term_id)
+        .bind(filter.academic_year_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     Ok(Json(rows))
 }
 
-async fn stats_absence_count(State(pool): State<PgPool>, user: User)
+async fn stats_absence_count(State(pool): State<PgPool>, user: User, Query(filter): Query<RecordFilter>)
     -> Result<Json<Vec<StudentAbsenceCount>>, (StatusCode, String)>
 {
-    require_role(&user, &[Role::Admin, Role::Director, Role::Teacher, Role::Parent, Role::Student])?;
-    let rows = sqlx::query!(
-        r#"SELECT student_id, COUNT(*) as "absence_count!" FROM absences GROUP BY student_id"#
-    )
-    .fetch_all(&pool)
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    let stats = rows.into_iter().map(|row| StudentAbsenceCount {
-        student_id: row.student_id,
-        absence_count: row.absence_count,
-    }).collect();
+    authorize(&user, Action::Read, &Resource::Stats)?;
+    let scope = student_scope(&user).unwrap_or_else(|| "TRUE".to_string());
+    let sql = format!(
+        "SELECT student_id, COUNT(*) AS absence_count FROM absences WHERE student_id IN (SELECT s.id FROM students s WHERE ($2::uuid IS NULL OR s.school_id = $2) AND {}) AND {} GROUP BY student_id",
+        scope, TERM_FILTER
+    );
+
+    let stats = sqlx::query_as::<_, StudentAbsenceCount>(&sql)
+        .bind(user.id)
+        .bind(filter.school_id)
+        .bind(filter.term_id)
+        .bind(filter.academic_year_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     Ok(Json(stats))
 }
 
@@ -626,9 +3377,19 @@ async fn stats_absence_count(State(pool): State<PgPool>, user: User)
 #[tokio::main]
 async fn main() {
     dotenvy::dotenv().ok();
+    tracing_subscriber::fmt::init();
 
-    let database_url = std::env::var("DATABASE_URL").expect("Missing DATABASE_URL");
-    let pool = PgPool::connect(&database_url).await.expect("Failed DB connection");
+    let state = AppState {
+        pool: db::connect_db().await,
+        auth: AuthConfig::from_env(),
+        providers: AuthProviders::from_env(),
+        lockout: LockoutPolicy::from_env(),
+        mfa: MfaPolicy::from_env(),
+        reset: ResetConfig::from_env(),
+        impersonation: ImpersonationConfig::from_env(),
+        passwords: PasswordPolicy::from_env(),
+        mailer: mailer::from_env(),
+    };
 
     let cors = CorsLayer::new()
     .allow_origin(Any)           // Allow all origins; for production, specify your frontend origin
@@ -637,11 +3398,45 @@ async fn main() {
 
     let app = Router::new()
         .route("/health", get(health_check))
+        // OWN PROFILE
+        .route("/me", get(get_me).put(update_me))
         // USERS
         .route("/users", post(create_user).get(list_users))
         .route("/users/:id", put(update_user))
         .route("/users/:id", delete(delete_user))
+        .route("/users/:id/sessions", delete(revoke_user_sessions))
+        .route("/users/:id/unlock", post(unlock_user))
+        .route("/users/:id/totp", delete(reset_user_totp))
+        .route("/users/:id/impersonate", post(impersonate_user))
+        .route("/impersonation_log", get(impersonation_log))
+        // INVITES & REGISTRATION
+        .route("/invites", post(create_invite).get(list_invites))
+        .route("/invites/:id", delete(delete_invite))
+        .route("/register", post(register))
+
+        .route("/api_keys", post(create_api_key).get(list_api_keys))
+        .route("/api_keys/:id", delete(revoke_api_key))
         // STUDENTS
+        .route("/schools", post(create_school).get(list_schools))
+        .route("/schools/:id", get(get_school).put(update_school).delete(delete_school))
+
+        .route("/classes", post(create_class).get(list_classes))
+        .route("/classes/:id", get(get_class).put(update_class).delete(delete_class))
+        .route("/classes/:id/students", get(class_roster))
+
+        .route("/academic_years", post(create_academic_year).get(list_academic_years))
+        .route("/academic_years/:id", get(get_academic_year).put(update_academic_year).delete(delete_academic_year))
+        .route("/terms", post(create_term).get(list_terms))
+        .route("/terms/:id", get(get_term).put(update_term).delete(delete_term))
+
+        .route("/subjects", post(create_subject).get(list_subjects))
+        .route("/subjects/:id", get(get_subject).put(update_subject).delete(delete_subject))
+        .route("/subjects/:id/merge", post(merge_subjects))
+
+        .route("/teachers", get(list_teachers))
+        .route("/teachers/:id", get(get_teacher).put(update_teacher))
+        .route("/teachers/:id/subjects", put(set_teacher_subjects))
+
         .route("/students", post(create_student).get(list_students))
         .route("/students/:id", get(get_student))
         .route("/students/:id", put(update_student))
@@ -655,6 +3450,10 @@ async fn main() {
         .route("/parent_students/:parent_id", get(students_for_parent))
         .route("/parent_students/:parent_id/:student_id", delete(delete_parent_student))
         // ABSENCES
+        .route("/curriculum", post(create_curriculum_entry).get(list_curriculum))
+        .route("/curriculum/copy", post(copy_curriculum))
+        .route("/curriculum/:id", get(get_curriculum_entry).put(update_curriculum_entry).delete(delete_curriculum_entry))
+
         .route("/absences", post(create_absence).get(list_absences))
         .route("/absences/:id", put(update_absence))
         .route("/absences/:id", delete(delete_absence))
@@ -663,13 +3462,881 @@ async fn main() {
         .route("/stats/absence_count", get(stats_absence_count))
         // LOGIN (public)
         .route("/login", post(login))
+        .route("/auth/refresh", post(refresh_session))
+        .route("/auth/logout", post(logout))
+        .route("/auth/forgot", post(forgot_password))
+        .route("/auth/reset", post(reset_password))
+        .route("/login/totp", post(login_totp))
+        .route("/login/totp/enroll", post(login_totp_enroll))
+        .route("/auth/totp", post(setup_totp).delete(disable_totp))
+        .route("/auth/totp/confirm", post(confirm_totp))
         .layer(cors) 
-        .with_state(pool);
+        .with_state(state);
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
     println!("Server running at http://{}", addr);
 
-    axum::serve(tokio::net::TcpListener::bind(addr).await.unwrap(), app.into_make_service())
+    axum::serve(tokio::net::TcpListener::bind(addr).await.unwrap(), app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{directory_user, insert_school, insert_user, outbox_messages, user};
+
+    // === Password reset ===
+    #[sqlx::test]
+    async fn rejected_reset_password_changes_nothing(pool: PgPool) {
+        let user = insert_user(&pool, "a@example.com", &[Role::Teacher], None).await;
+        let token = password_reset::issue(&pool, user.id, chrono::Duration::minutes(30)).await.unwrap();
+        let hash_before: String = sqlx::query_scalar("SELECT hashed_password FROM users WHERE id = $1")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        let weak = ResetPasswordRequest { token: token.clone(), new_password: "short".to_string() };
+        let result = reset_password(State(pool.clone()), State(PasswordPolicy::from_env()), Json(weak)).await;
+        assert!(matches!(result, Err(PasswordChangeError::Policy(_))));
+
+        let hash_after: String = sqlx::query_scalar("SELECT hashed_password FROM users WHERE id = $1")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(hash_before, hash_after);
+
+        // The token wasn't spent on the rejected attempt
+        let strong = ResetPasswordRequest { token, new_password: "A much better passphrase".to_string() };
+        let result = reset_password(State(pool.clone()), State(PasswordPolicy::from_env()), Json(strong)).await;
+        assert!(matches!(result, Ok(StatusCode::NO_CONTENT)));
+    }
+
+    #[sqlx::test]
+    async fn forgot_password_mails_only_local_accounts(pool: PgPool) {
+        insert_user(&pool, "local@example.com", &[Role::Teacher], None).await;
+        directory_user(&pool, "directory@example.com").await;
+        let outbox = tempfile::tempdir().unwrap();
+        let mailer: Arc<dyn Mailer> = Arc::new(mailer::OutboxMailer::new(outbox.path(), "test"));
+
+        for email in ["directory@example.com", "nobody@example.com"] {
+            send_reset_link(pool.clone(), ResetConfig::from_env(), mailer.clone(), email.to_string()).await.unwrap();
+            assert!(outbox_messages(outbox.path()).is_empty(), "{}", email);
+        }
+        send_reset_link(pool.clone(), ResetConfig::from_env(), mailer.clone(), "local@example.com".to_string()).await.unwrap();
+        let messages = outbox_messages(outbox.path());
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("To: local@example.com"));
+    }
+
+    // === Directory accounts ===
+    #[sqlx::test]
+    async fn directory_accounts_cannot_change_their_password(pool: PgPool) {
+        let user = directory_user(&pool, "jane@example.com").await;
+        let session = AuthSession { user, session_id: Uuid::new_v4(), impersonator_id: None };
+        let input = UpdateMe {
+            email: None,
+            first_name: None,
+            last_name: None,
+            current_password: Some("directory pass 1".to_string()),
+            new_password: Some("A much better passphrase".to_string()),
+        };
+        let result = update_me(State(pool.clone()), State(PasswordPolicy::from_env()), session, Json(input)).await;
+        assert!(matches!(result, Err(PasswordChangeError::Status(StatusCode::CONFLICT, _))));
+    }
+
+    #[sqlx::test]
+    async fn admins_cannot_set_a_password_for_directory_accounts(pool: PgPool) {
+        let admin = insert_user(&pool, "admin@example.com", &[Role::Admin], None).await;
+        let user = directory_user(&pool, "jane@example.com").await;
+        let input = UpdateUser {
+            email: None,
+            password: Some("A much better passphrase".to_string()),
+            roles: None,
+            first_name: None,
+            last_name: None,
+            school_id: None,
+        };
+        let result = update_user(State(pool.clone()), State(PasswordPolicy::from_env()), admin, Path(user.id), Json(input)).await;
+        assert!(matches!(result, Err(PasswordChangeError::Status(StatusCode::CONFLICT, _))));
+
+        let hash: String = sqlx::query_scalar("SELECT hashed_password FROM users WHERE id = $1")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(hash, "!directory");
+    }
+
+    // === Tenancy ===
+    // A school with one of everything: its director, the homeroom teacher of its
+    // class, a student of that class with a grade and an absence, and the student's parent
+    struct SchoolFixture {
+        school_id: Uuid,
+        director: User,
+        teacher: User,
+        parent: User,
+        class_id: Uuid,
+        student_id: Uuid,
+        grade_id: Uuid,
+        subject_id: Uuid,
+    }
+
+    async fn school_fixture(pool: &PgPool, name: &str, subject_id: Uuid) -> SchoolFixture {
+        let school_id = insert_school(pool, name).await;
+        let email = |who: &str| format!("{}@{}.example.com", who, name.to_lowercase());
+        let director = insert_user(pool, &email("director"), &[Role::Director], Some(school_id)).await;
+        let teacher = insert_user(pool, &email("teacher"), &[Role::Teacher], Some(school_id)).await;
+        let parent = insert_user(pool, &email("parent"), &[Role::Parent], Some(school_id)).await;
+        let student = insert_user(pool, &email("student"), &[Role::Student], Some(school_id)).await;
+
+        let year_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO academic_years (school_id, name, starts_on, ends_on) VALUES ($1, '2026/2027', '2026-09-15', '2027-06-30') RETURNING id")
+            .bind(school_id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        let class_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO classes (school_id, grade_level, letter, academic_year_id, homeroom_teacher_id) VALUES ($1, 10, 'A', $2, $3) RETURNING id")
+            .bind(school_id)
+            .bind(year_id)
+            .bind(teacher.id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        let student_id: Uuid = sqlx::query_scalar("INSERT INTO students (user_id, class_id, school_id) VALUES ($1, $2, $3) RETURNING id")
+            .bind(student.id)
+            .bind(class_id)
+            .bind(school_id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO parent_students (parent_id, student_id) VALUES ($1, $2)")
+            .bind(parent.id)
+            .bind(student_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        let grade_id: Uuid = sqlx::query_scalar("INSERT INTO grades (student_id, subject_id, value, teacher_id) VALUES ($1, $2, 5, $3) RETURNING id")
+            .bind(student_id)
+            .bind(subject_id)
+            .bind(teacher.id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO absences (student_id, date) VALUES ($1, '2026-10-01')")
+            .bind(student_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        SchoolFixture { school_id, director, teacher, parent, class_id, student_id, grade_id, subject_id }
+    }
+
+    // Two schools side by side
+    async fn two_schools(pool: &PgPool) -> (SchoolFixture, SchoolFixture) {
+        let subject_id: Uuid = sqlx::query_scalar(r#"INSERT INTO subjects (code, names) VALUES ('MATH', '{"en": "Mathematics"}') RETURNING id"#)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        (school_fixture(pool, "North", subject_id).await, school_fixture(pool, "South", subject_id).await)
+    }
+
+    fn no_filter() -> RecordFilter {
+        RecordFilter { school_id: None, term_id: None, academic_year_id: None }
+    }
+
+    fn school_filter(school_id: Uuid) -> RecordFilter {
+        RecordFilter { school_id: Some(school_id), term_id: None, academic_year_id: None }
+    }
+
+    fn status<T>(result: Result<T, (StatusCode, String)>) -> StatusCode {
+        match result {
+            Ok(_) => StatusCode::OK,
+            Err((status, _)) => status,
+        }
+    }
+
+    #[test]
+    fn tenant_condition_follows_the_users_school() {
+        let school_id = Uuid::from_u128(7);
+        assert_eq!(tenant_condition(&user(&[Role::Admin], None), "s.school_id"), "TRUE");
+        assert_eq!(tenant_condition(&user(&[Role::Director], Some(school_id)), "s.school_id"), format!("s.school_id = '{}'", school_id));
+        assert_eq!(tenant_condition(&user(&[Role::Teacher, Role::Parent], Some(school_id)), "c.school_id"), format!("c.school_id = '{}'", school_id));
+        assert_eq!(tenant_condition(&user(&[Role::Director], None), "s.school_id"), "FALSE");
+    }
+
+    #[sqlx::test]
+    async fn policy_keeps_other_schools_out(pool: PgPool) {
+        let (north, south) = two_schools(&pool).await;
+        for user in [&south.director, &south.teacher, &south.parent] {
+            let own = student_facts(&pool, user, south.student_id).await.unwrap();
+            assert!(policy::can(user, Action::Read, &Resource::Student(&own)));
+            let other = student_facts(&pool, user, north.student_id).await.unwrap();
+            assert!(!policy::can(user, Action::Read, &Resource::Student(&other)));
+
+            let own = class_facts(&pool, user, south.class_id).await.unwrap();
+            let other = class_facts(&pool, user, north.class_id).await.unwrap();
+            if !user.has_role(Role::Parent) {
+                assert!(policy::can(user, Action::Read, &Resource::Class(&own)));
+            }
+            assert!(!policy::can(user, Action::Read, &Resource::Class(&other)));
+        }
+    }
+
+    #[sqlx::test]
+    async fn students_of_other_schools_are_hidden(pool: PgPool) {
+        let (north, south) = two_schools(&pool).await;
+        for user in [&south.director, &south.teacher, &south.parent] {
+            let students = list_students(State(pool.clone()), user.clone(), Query(SchoolFilter { school_id: None })).await.unwrap();
+            let ids: Vec<Uuid> = students.0.iter().map(|s| s.id).collect();
+            assert_eq!(ids, vec![south.student_id]);
+
+            let students = list_students(State(pool.clone()), user.clone(), Query(SchoolFilter { school_id: Some(north.school_id) })).await.unwrap();
+            assert!(students.0.is_empty());
+
+            let result = get_student(State(pool.clone()), user.clone(), Path(north.student_id)).await;
+            assert_eq!(status(result), StatusCode::FORBIDDEN);
+        }
+    }
+
+    #[sqlx::test]
+    async fn classes_of_other_schools_are_hidden(pool: PgPool) {
+        let (north, south) = two_schools(&pool).await;
+        for user in [&south.director, &south.teacher, &south.parent] {
+            let query = ClassQuery { school_id: None, academic_year_id: None };
+            let classes = list_classes(State(pool.clone()), user.clone(), Query(query)).await.unwrap();
+            assert!(classes.0.iter().all(|c| c.school_id == south.school_id));
+
+            let query = ClassQuery { school_id: Some(north.school_id), academic_year_id: None };
+            let classes = list_classes(State(pool.clone()), user.clone(), Query(query)).await.unwrap();
+            assert!(classes.0.is_empty());
+
+            let result = get_class(State(pool.clone()), user.clone(), Path(north.class_id)).await;
+            assert_eq!(status(result), StatusCode::FORBIDDEN);
+            let result = class_roster(State(pool.clone()), user.clone(), Path(north.class_id)).await;
+            assert_eq!(status(result), StatusCode::FORBIDDEN);
+        }
+    }
+
+    #[sqlx::test]
+    async fn grades_and_absences_of_other_schools_are_hidden(pool: PgPool) {
+        let (north, south) = two_schools(&pool).await;
+        for user in [&south.director, &south.teacher, &south.parent] {
+            let grades = list_grades(State(pool.clone()), user.clone(), Query(no_filter())).await.unwrap();
+            assert_eq!(grades.0.iter().map(|g| g.id).collect::<Vec<_>>(), vec![south.grade_id]);
+            let grades = list_grades(State(pool.clone()), user.clone(), Query(school_filter(north.school_id))).await.unwrap();
+            assert!(grades.0.is_empty());
+
+            let absences = list_absences(State(pool.clone()), user.clone(), Query(no_filter())).await.unwrap();
+            assert!(absences.0.iter().all(|a| a.student_id == south.student_id));
+            assert_eq!(absences.0.len(), 1);
+            let absences = list_absences(State(pool.clone()), user.clone(), Query(school_filter(north.school_id))).await.unwrap();
+            assert!(absences.0.is_empty());
+        }
+    }
+
+    #[sqlx::test]
+    async fn grades_and_absences_of_other_schools_are_untouchable(pool: PgPool) {
+        let (north, south) = two_schools(&pool).await;
+        let subject_id: Uuid = sqlx::query_scalar("SELECT id FROM subjects").fetch_one(&pool).await.unwrap();
+        for user in [&south.director, &south.teacher, &south.parent] {
+            let input = NewGrade { student_id: north.student_id, subject_id, value: 2, teacher_id: Some(south.teacher.id) };
+            let result = create_grade(State(pool.clone()), user.clone(), Json(input)).await;
+            assert_eq!(status(result), StatusCode::FORBIDDEN);
+
+            let input = UpdateGrade { value: Some(2), subject_id: None };
+            let result = update_grade(State(pool.clone()), user.clone(), Path(north.grade_id), Json(input)).await;
+            assert_eq!(status(result), StatusCode::FORBIDDEN);
+
+            let result = delete_grade(State(pool.clone()), user.clone(), Path(north.grade_id)).await;
+            assert_eq!(status(result), StatusCode::FORBIDDEN);
+
+            let input = NewAbsence { student_id: north.student_id, date: chrono::NaiveDate::from_ymd_opt(2026, 10, 2).unwrap(), reason: None };
+            let result = create_absence(State(pool.clone()), user.clone(), Json(input)).await;
+            assert_eq!(status(result), StatusCode::FORBIDDEN);
+        }
+
+        let (grades, absences): (i64, i64) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM grades g WHERE g.student_id = $1 AND g.value = 5), (SELECT COUNT(*) FROM absences a WHERE a.student_id = $1)")
+            .bind(north.student_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!((grades, absences), (1, 1));
+    }
+
+    #[sqlx::test]
+    async fn statistics_leave_other_schools_out(pool: PgPool) {
+        let (north, south) = two_schools(&pool).await;
+        for user in [&south.director, &south.teacher, &south.parent] {
+            let averages = stats_avg_grade(State(pool.clone()), user.clone(), Query(no_filter())).await.unwrap();
+            assert_eq!(averages.0.iter().map(|s| s.student_id).collect::<Vec<_>>(), vec![south.student_id]);
+            let averages = stats_avg_grade(State(pool.clone()), user.clone(), Query(school_filter(north.school_id))).await.unwrap();
+            assert!(averages.0.is_empty());
+
+            let counts = stats_absence_count(State(pool.clone()), user.clone(), Query(no_filter())).await.unwrap();
+            assert_eq!(counts.0.iter().map(|s| s.student_id).collect::<Vec<_>>(), vec![south.student_id]);
+            let counts = stats_absence_count(State(pool.clone()), user.clone(), Query(school_filter(north.school_id))).await.unwrap();
+            assert!(counts.0.is_empty());
+        }
+    }
+
+    // === Classes ===
+    #[sqlx::test]
+    async fn classes_belong_to_a_year_of_their_school(pool: PgPool) {
+        let (north, south) = two_schools(&pool).await;
+        let year_of = |school_id: Uuid| {
+            sqlx::query_scalar::<_, Uuid>("SELECT id FROM academic_years WHERE school_id = $1")
+                .bind(school_id)
+                .fetch_one(&pool)
+        };
+        let north_year = year_of(north.school_id).await.unwrap();
+        let south_year = year_of(south.school_id).await.unwrap();
+
+        let class = |academic_year_id| NewClass { school_id: None, grade_level: 11, letter: "b".to_string(), academic_year_id, homeroom_teacher_id: None };
+        let result = create_class(State(pool.clone()), south.director.clone(), Json(class(north_year))).await;
+        assert_eq!(status(result), StatusCode::BAD_REQUEST);
+        let (_, Json(created)) = create_class(State(pool.clone()), south.director.clone(), Json(class(south_year))).await.unwrap();
+        assert_eq!((created.academic_year_id, created.letter.as_str()), (south_year, "B"));
+
+        let query = ClassQuery { school_id: None, academic_year_id: Some(south_year) };
+        let classes = list_classes(State(pool.clone()), south.director.clone(), Query(query)).await.unwrap();
+        assert_eq!(classes.0.len(), 2);
+
+        // The year stays while classes are in it
+        let result = delete_academic_year(State(pool.clone()), south.director.clone(), Path(south_year)).await;
+        assert_eq!(status(result), StatusCode::CONFLICT);
+    }
+
+    // === Sessions ===
+    // Just what the AuthSession extractor needs from the app state
+    #[derive(Clone)]
+    struct SessionState {
+        pool: PgPool,
+        auth: AuthConfig,
+    }
+
+    impl FromRef<SessionState> for PgPool {
+        fn from_ref(state: &SessionState) -> Self {
+            state.pool.clone()
+        }
+    }
+
+    impl FromRef<SessionState> for AuthConfig {
+        fn from_ref(state: &SessionState) -> Self {
+            state.auth.clone()
+        }
+    }
+
+    fn session_state(pool: &PgPool) -> SessionState {
+        let auth = AuthConfig::new("a test secret that is long enough to sign with", chrono::Duration::minutes(15), chrono::Duration::days(14));
+        SessionState { pool: pool.clone(), auth }
+    }
+
+    // Runs the AuthSession extractor for a request with the access token
+    async fn authenticate(state: &SessionState, method: &str, path: &str, access_token: &str) -> Result<AuthSession, (StatusCode, String)> {
+        let request = axum::http::Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+            .body(())
+            .unwrap();
+        let (mut parts, _) = request.into_parts();
+        AuthSession::from_request_parts(&mut parts, state).await
+    }
+
+    async fn refresh(state: &SessionState, refresh_token: &str) -> Result<LoginResponse, (StatusCode, String)> {
+        let input = RefreshRequest { refresh_token: refresh_token.to_string() };
+        refresh_session(State(state.pool.clone()), State(state.auth.clone()), Json(input)).await.map(|Json(tokens)| tokens)
+    }
+
+    #[sqlx::test]
+    async fn refresh_tokens_work_once(pool: PgPool) {
+        let state = session_state(&pool);
+        let user = insert_user(&pool, "a@example.com", &[Role::Teacher], None).await;
+        let first = open_session(&pool, &state.auth, user).await.unwrap();
+
+        let second = refresh(&state, &first.refresh_token).await.unwrap();
+        assert_ne!(second.refresh_token, first.refresh_token);
+        assert!(authenticate(&state, "GET", "/me", &second.access_token).await.is_ok());
+        let third = refresh(&state, &second.refresh_token).await.unwrap();
+        assert!(authenticate(&state, "GET", "/me", &third.access_token).await.is_ok());
+        assert_eq!(status(refresh(&state, "not a refresh token").await), StatusCode::UNAUTHORIZED);
+    }
+
+    #[sqlx::test]
+    async fn reusing_a_rotated_refresh_token_revokes_the_session(pool: PgPool) {
+        let state = session_state(&pool);
+        let user = insert_user(&pool, "a@example.com", &[Role::Teacher], None).await;
+        let other_session = open_session(&pool, &state.auth, user.clone()).await.unwrap();
+        let first = open_session(&pool, &state.auth, user).await.unwrap();
+        let second = refresh(&state, &first.refresh_token).await.unwrap();
+        let third = refresh(&state, &second.refresh_token).await.unwrap();
+
+        // Any earlier token of the session counts, not just the last one
+        assert_eq!(status(refresh(&state, &first.refresh_token).await), StatusCode::UNAUTHORIZED);
+        assert_eq!(status(refresh(&state, &third.refresh_token).await), StatusCode::UNAUTHORIZED);
+        assert_eq!(status(authenticate(&state, "GET", "/me", &third.access_token).await), StatusCode::UNAUTHORIZED);
+
+        // The user's other sessions are left alone
+        assert!(authenticate(&state, "GET", "/me", &other_session.access_token).await.is_ok());
+        assert!(refresh(&state, &other_session.refresh_token).await.is_ok());
+    }
+
+    #[sqlx::test]
+    async fn revoked_sessions_stop_working(pool: PgPool) {
+        let state = session_state(&pool);
+        let user = insert_user(&pool, "a@example.com", &[Role::Teacher], None).await;
+        let kept = open_session(&pool, &state.auth, user.clone()).await.unwrap();
+        let tokens = open_session(&pool, &state.auth, user.clone()).await.unwrap();
+
+        let session = authenticate(&state, "POST", "/auth/logout", &tokens.access_token).await.unwrap();
+        assert_eq!(logout(State(pool.clone()), session).await.unwrap(), StatusCode::NO_CONTENT);
+        assert_eq!(status(authenticate(&state, "GET", "/me", &tokens.access_token).await), StatusCode::UNAUTHORIZED);
+        assert_eq!(status(refresh(&state, &tokens.refresh_token).await), StatusCode::UNAUTHORIZED);
+        assert!(authenticate(&state, "GET", "/me", &kept.access_token).await.is_ok());
+
+        // Revoking all of a user's sessions (role or password changed by an admin)
+        sessions::revoke_all_for_user(&pool, user.id).await.unwrap();
+        assert_eq!(status(authenticate(&state, "GET", "/me", &kept.access_token).await), StatusCode::UNAUTHORIZED);
+        assert_eq!(status(refresh(&state, &kept.refresh_token).await), StatusCode::UNAUTHORIZED);
+    }
+
+    // === Impersonation ===
+    async fn impersonate(state: &SessionState, admin: &User, target: &User) -> Result<ImpersonationResponse, (StatusCode, String)> {
+        let session = AuthSession { user: admin.clone(), session_id: Uuid::new_v4(), impersonator_id: None };
+        let config = ImpersonationConfig { ttl: chrono::Duration::minutes(30) };
+        impersonate_user(State(state.pool.clone()), State(state.auth.clone()), State(config), session, Path(target.id))
+            .await
+            .map(|Json(response)| response)
+    }
+
+    #[sqlx::test]
+    async fn impersonation_is_read_only_and_logged(pool: PgPool) {
+        let state = session_state(&pool);
+        let admin = insert_user(&pool, "admin@example.com", &[Role::Admin], None).await;
+        let teacher = insert_user(&pool, "teacher@example.com", &[Role::Teacher], None).await;
+        let token = impersonate(&state, &admin, &teacher).await.unwrap().access_token;
+
+        let session = authenticate(&state, "GET", "/grades", &token).await.unwrap();
+        assert_eq!(session.user.id, teacher.id);
+        assert_eq!(session.impersonator_id, Some(admin.id));
+        for method in ["POST", "PUT", "DELETE"] {
+            assert_eq!(status(authenticate(&state, method, "/grades", &token).await), StatusCode::FORBIDDEN);
+        }
+
+        let session = authenticate(&state, "POST", "/auth/logout", &token).await.unwrap();
+        logout(State(pool.clone()), session).await.unwrap();
+        assert_eq!(status(authenticate(&state, "GET", "/grades", &token).await), StatusCode::UNAUTHORIZED);
+
+        let mut log: Vec<(String, String, bool)> = impersonation::recent(&pool, Some(admin.id), 100)
+            .await
+            .unwrap()
+            .into_iter()
+            .inspect(|entry| assert_eq!((entry.impersonator_id, entry.user_id), (admin.id, teacher.id)))
+            .map(|entry| (entry.method, entry.path, entry.allowed))
+            .collect();
+        log.sort();
+        let entry = |method: &str, path: &str, allowed| (method.to_string(), path.to_string(), allowed);
+        assert_eq!(log, vec![
+            entry("DELETE", "/grades", false),
+            entry("GET", "/grades", true),
+            entry("POST", "/auth/logout", true),
+            entry("POST", "/grades", false),
+            entry("POST", &format!("/users/{}/impersonate", teacher.id), true),
+            entry("PUT", "/grades", false),
+        ]);
+    }
+
+    #[sqlx::test]
+    async fn admins_cannot_impersonate_admins(pool: PgPool) {
+        let state = session_state(&pool);
+        let admin = insert_user(&pool, "admin@example.com", &[Role::Admin], None).await;
+        let other_admin = insert_user(&pool, "other@example.com", &[Role::Admin, Role::Teacher], None).await;
+        let director = insert_user(&pool, "director@example.com", &[Role::Director], None).await;
+        let teacher = insert_user(&pool, "teacher@example.com", &[Role::Teacher], None).await;
+
+        assert_eq!(status(impersonate(&state, &admin, &other_admin).await), StatusCode::FORBIDDEN);
+        assert_eq!(status(impersonate(&state, &director, &teacher).await), StatusCode::FORBIDDEN);
+        let sessions: i64 = sqlx::query_scalar("SELECT count(*) FROM sessions").fetch_one(&pool).await.unwrap();
+        assert_eq!(sessions, 0);
+    }
+
+    // === Invites ===
+    async fn invite(pool: &PgPool, inviter: &User, role: Role, student_id: Option<Uuid>) -> String {
+        let input = NewInvite { role, student_id, school_id: None, expires_in_days: None };
+        create_invite(State(pool.clone()), inviter.clone(), Json(input)).await.unwrap().0.code
+    }
+
+    async fn redeem(pool: &PgPool, code: &str, email: &str) -> Result<(StatusCode, Json<RegisterResponse>), PasswordChangeError> {
+        let input = Registration {
+            code: code.to_string(),
+            email: email.to_string(),
+            password: "Fresh password 2".to_string(),
+            first_name: "New".to_string(),
+            last_name: "Member".to_string(),
+        };
+        register(State(pool.clone()), State(PasswordPolicy::from_env()), Json(input)).await
+    }
+
+    fn is_refused<T>(result: &Result<T, PasswordChangeError>) -> bool {
+        matches!(result, Err(PasswordChangeError::Status(StatusCode::BAD_REQUEST, _)))
+    }
+
+    async fn user_count(pool: &PgPool) -> i64 {
+        sqlx::query_scalar("SELECT count(*) FROM users").fetch_one(pool).await.unwrap()
+    }
+
+    #[sqlx::test]
+    async fn invites_are_redeemed_once(pool: PgPool) {
+        let (_, south) = two_schools(&pool).await;
+        let code = invite(&pool, &south.director, Role::Teacher, None).await;
+
+        // Typed in by hand: case and dashes don't matter
+        let (status, Json(registered)) = redeem(&pool, &code.to_lowercase().replace('-', ""), "first@example.com").await.ok().unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(registered.role, Role::Teacher);
+        let school_id: Option<Uuid> = sqlx::query_scalar("SELECT school_id FROM users WHERE id = $1")
+            .bind(registered.user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(school_id, Some(south.school_id));
+
+        let users = user_count(&pool).await;
+        assert!(is_refused(&redeem(&pool, &code, "second@example.com").await));
+        assert_eq!(user_count(&pool).await, users);
+    }
+
+    #[sqlx::test]
+    async fn concurrent_redemptions_of_an_invite_make_one_account(pool: PgPool) {
+        let (_, south) = two_schools(&pool).await;
+        let code = invite(&pool, &south.director, Role::Teacher, None).await;
+        let users = user_count(&pool).await;
+
+        let (first, second) = tokio::join!(redeem(&pool, &code, "first@example.com"), redeem(&pool, &code, "second@example.com"));
+        assert_eq!([&first, &second].iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(is_refused(&first) || is_refused(&second));
+        assert_eq!(user_count(&pool).await, users + 1);
+    }
+
+    #[sqlx::test]
+    async fn expired_invites_are_refused(pool: PgPool) {
+        let (_, south) = two_schools(&pool).await;
+        let code = invite(&pool, &south.director, Role::Teacher, None).await;
+        sqlx::query("UPDATE invites SET expires_at = now() - interval '1 minute'").execute(&pool).await.unwrap();
+
+        let users = user_count(&pool).await;
+        assert!(is_refused(&redeem(&pool, &code, "late@example.com").await));
+        assert_eq!(user_count(&pool).await, users);
+    }
+
+    #[sqlx::test]
+    async fn parent_invites_link_the_student(pool: PgPool) {
+        let (_, south) = two_schools(&pool).await;
+        let code = invite(&pool, &south.director, Role::Parent, Some(south.student_id)).await;
+
+        let (_, Json(registered)) = redeem(&pool, &code, "guardian@example.com").await.ok().unwrap();
+        assert_eq!(registered.role, Role::Parent);
+        let children: Vec<Uuid> = sqlx::query_scalar("SELECT student_id FROM parent_students WHERE parent_id = $1")
+            .bind(registered.user_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(children, vec![south.student_id]);
+    }
+
+    #[sqlx::test]
+    async fn invites_can_be_listed_per_school(pool: PgPool) {
+        let (north, south) = two_schools(&pool).await;
+        invite(&pool, &north.director, Role::Teacher, None).await;
+        invite(&pool, &south.director, Role::Teacher, None).await;
+        let admin = insert_user(&pool, "admin@example.com", &[Role::Admin], None).await;
+        let schools = |invites: Vec<Invite>| invites.into_iter().map(|invite| invite.school_id).collect::<Vec<_>>();
+
+        let Json(all) = list_invites(State(pool.clone()), admin.clone(), Query(SchoolFilter { school_id: None })).await.unwrap();
+        assert_eq!(all.len(), 2);
+        let Json(north_only) = list_invites(State(pool.clone()), admin, Query(SchoolFilter { school_id: Some(north.school_id) })).await.unwrap();
+        assert_eq!(schools(north_only), vec![Some(north.school_id)]);
+        // The filter narrows a director's list down but never widens it
+        let Json(none) = list_invites(State(pool.clone()), south.director, Query(SchoolFilter { school_id: Some(north.school_id) })).await.unwrap();
+        assert!(none.is_empty());
+    }
+
+    // === Users ===
+    fn rejected_with<T>(result: &Result<T, PasswordChangeError>) -> Option<StatusCode> {
+        match result {
+            Err(PasswordChangeError::Status(status, _)) => Some(*status),
+            _ => None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn taken_emails_are_a_conflict(pool: PgPool) {
+        let admin = insert_user(&pool, "admin@example.com", &[Role::Admin], None).await;
+        let taken = insert_user(&pool, "taken@example.com", &[Role::Teacher], None).await;
+        let other = insert_user(&pool, "other@example.com", &[Role::Teacher], None).await;
+
+        let input = NewUser {
+            email: taken.email.clone(),
+            password: "Fresh password 2".to_string(),
+            roles: vec![Role::Teacher],
+            first_name: "New".to_string(),
+            last_name: "Member".to_string(),
+            school_id: None,
+        };
+        let result = create_user(State(pool.clone()), State(PasswordPolicy::from_env()), admin.clone(), Json(input)).await;
+        assert_eq!(rejected_with(&result), Some(StatusCode::CONFLICT));
+
+        let input = UpdateUser {
+            email: Some(taken.email.clone()),
+            password: None,
+            roles: None,
+            first_name: None,
+            last_name: None,
+            school_id: None,
+        };
+        let result = update_user(State(pool.clone()), State(PasswordPolicy::from_env()), admin, Path(other.id), Json(input)).await;
+        assert_eq!(rejected_with(&result), Some(StatusCode::CONFLICT));
+    }
+
+    #[sqlx::test]
+    async fn users_can_be_moved_out_of_their_school(pool: PgPool) {
+        let admin = insert_user(&pool, "admin@example.com", &[Role::Admin], None).await;
+        let school_id = insert_school(&pool, "North").await;
+        let teacher = insert_user(&pool, "teacher@example.com", &[Role::Teacher], Some(school_id)).await;
+        let update = |body: &str| {
+            let input: UpdateUser = serde_json::from_str(body).unwrap();
+            update_user(State(pool.clone()), State(PasswordPolicy::from_env()), admin.clone(), Path(teacher.id), Json(input))
+        };
+
+        let Ok(Json(updated)) = update(r#"{ "first_name": "Jane" }"#).await else { panic!("update refused") };
+        assert_eq!(updated.school_id, Some(school_id));
+        let Ok(Json(updated)) = update(r#"{ "school_id": null }"#).await else { panic!("update refused") };
+        assert_eq!(updated.school_id, None);
+        let Ok(Json(updated)) = update(&format!(r#"{{ "school_id": "{}" }}"#, school_id)).await else { panic!("update refused") };
+        assert_eq!(updated.school_id, Some(school_id));
+    }
+
+    // === Teaching ===
+    // A term of the school's year around today, the day new grades are given on
+    async fn current_term(pool: &PgPool, school: &SchoolFixture) -> Uuid {
+        let today = chrono::Utc::now().date_naive();
+        let year_id: Uuid = sqlx::query_scalar(
+            "UPDATE academic_years SET starts_on = LEAST(starts_on, $2), ends_on = GREATEST(ends_on, $3) WHERE school_id = $1 RETURNING id")
+            .bind(school.school_id)
+            .bind(today - chrono::Days::new(90))
+            .bind(today + chrono::Days::new(90))
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        sqlx::query_scalar("INSERT INTO terms (academic_year_id, name, starts_on, ends_on) VALUES ($1, 'Current', $2, $3) RETURNING id")
+            .bind(year_id)
+            .bind(today - chrono::Days::new(30))
+            .bind(today + chrono::Days::new(30))
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn qualify(pool: &PgPool, teacher: &User, subject_id: Uuid) {
+        sqlx::query("INSERT INTO teacher_qualifications (teacher_id, subject_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(teacher.id)
+            .bind(subject_id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    // Puts the teacher on the class's curriculum for the subject in the term, returning the entry
+    async fn teach(pool: &PgPool, school: &SchoolFixture, teacher: &User, subject_id: Uuid, term_id: Uuid) -> Uuid {
+        qualify(pool, teacher, subject_id).await;
+        let input = NewCurriculumEntry { class_id: school.class_id, term_id, subject_id, weekly_hours: 4, teacher_ids: vec![teacher.id] };
+        let (_, Json(entry)) = create_curriculum_entry(State(pool.clone()), school.director.clone(), Json(input)).await.unwrap();
+        entry.id
+    }
+
+    async fn grade(pool: &PgPool, actor: &User, student_id: Uuid, subject_id: Uuid, teacher_id: Option<Uuid>) -> Result<Grade, (StatusCode, String)> {
+        let input = NewGrade { student_id, subject_id, value: 4, teacher_id };
+        create_grade(State(pool.clone()), actor.clone(), Json(input)).await.map(|Json(grade)| grade)
+    }
+
+    async fn absence(pool: &PgPool, actor: &User, student_id: Uuid) -> Result<Absence, (StatusCode, String)> {
+        let input = NewAbsence { student_id, date: chrono::Utc::now().date_naive(), reason: None };
+        create_absence(State(pool.clone()), actor.clone(), Json(input)).await.map(|Json(absence)| absence)
+    }
+
+    #[sqlx::test]
+    async fn teachers_grade_and_record_absences_only_where_they_teach(pool: PgPool) {
+        let (_, south) = two_schools(&pool).await;
+        let term_id = current_term(&pool, &south).await;
+        let assigned = insert_user(&pool, "assigned@example.com", &[Role::Teacher], Some(south.school_id)).await;
+        let unassigned = insert_user(&pool, "unassigned@example.com", &[Role::Teacher], Some(south.school_id)).await;
+        teach(&pool, &south, &assigned, south.subject_id, term_id).await;
+        qualify(&pool, &unassigned, south.subject_id).await;
+
+        let created = grade(&pool, &assigned, south.student_id, south.subject_id, None).await.unwrap();
+        assert_eq!((created.teacher_id, created.term_id), (assigned.id, Some(term_id)));
+        assert_eq!(status(grade(&pool, &unassigned, south.student_id, south.subject_id, None).await), StatusCode::FORBIDDEN);
+
+        let recorded = absence(&pool, &assigned, south.student_id).await.unwrap();
+        assert_eq!(recorded.term_id, Some(term_id));
+        assert_eq!(status(absence(&pool, &unassigned, south.student_id).await), StatusCode::FORBIDDEN);
+    }
+
+    #[sqlx::test]
+    async fn curriculum_teachers_must_be_qualified(pool: PgPool) {
+        let (north, south) = two_schools(&pool).await;
+        let term_id = current_term(&pool, &south).await;
+        let qualified = insert_user(&pool, "qualified@example.com", &[Role::Teacher], Some(south.school_id)).await;
+        let unqualified = insert_user(&pool, "unqualified@example.com", &[Role::Teacher], Some(south.school_id)).await;
+        qualify(&pool, &qualified, south.subject_id).await;
+        qualify(&pool, &north.teacher, south.subject_id).await;
+        let create = |teacher_ids: Vec<Uuid>| {
+            let input = NewCurriculumEntry { class_id: south.class_id, term_id, subject_id: south.subject_id, weekly_hours: 4, teacher_ids };
+            create_curriculum_entry(State(pool.clone()), south.director.clone(), Json(input))
+        };
+
+        assert_eq!(status(create(vec![qualified.id, unqualified.id]).await), StatusCode::BAD_REQUEST);
+        assert_eq!(status(create(vec![north.teacher.id]).await), StatusCode::BAD_REQUEST);
+        let (_, Json(entry)) = create(vec![qualified.id]).await.unwrap();
+
+        let update = |teacher_ids: Vec<Uuid>| {
+            let input = UpdateCurriculumEntry { weekly_hours: None, teacher_ids: Some(teacher_ids) };
+            update_curriculum_entry(State(pool.clone()), south.director.clone(), Path(entry.id), Json(input))
+        };
+        assert_eq!(status(update(vec![unqualified.id]).await), StatusCode::BAD_REQUEST);
+        qualify(&pool, &unqualified, south.subject_id).await;
+        let Json(updated) = update(vec![unqualified.id]).await.unwrap();
+        assert_eq!(updated.teacher_ids, vec![unqualified.id]);
+    }
+
+    #[sqlx::test]
+    async fn weekly_hours_are_between_1_and_40(pool: PgPool) {
+        let (_, south) = two_schools(&pool).await;
+        let term_id = current_term(&pool, &south).await;
+        let create = |weekly_hours: i16| {
+            let input = NewCurriculumEntry { class_id: south.class_id, term_id, subject_id: south.subject_id, weekly_hours, teacher_ids: Vec::new() };
+            create_curriculum_entry(State(pool.clone()), south.director.clone(), Json(input))
+        };
+        for hours in [-1, 0, 41] {
+            assert_eq!(status(create(hours).await), StatusCode::BAD_REQUEST);
+        }
+        let (_, Json(entry)) = create(40).await.unwrap();
+        assert_eq!(entry.weekly_hours, Some(40));
+
+        let update = |weekly_hours: i16| {
+            let input = UpdateCurriculumEntry { weekly_hours: Some(weekly_hours), teacher_ids: None };
+            update_curriculum_entry(State(pool.clone()), south.director.clone(), Path(entry.id), Json(input))
+        };
+        for hours in [0, 41] {
+            assert_eq!(status(update(hours).await), StatusCode::BAD_REQUEST);
+        }
+        let Json(updated) = update(1).await.unwrap();
+        assert_eq!(updated.weekly_hours, Some(1));
+    }
+
+    #[sqlx::test]
+    async fn grades_follow_the_curriculum_of_the_term(pool: PgPool) {
+        let (_, south) = two_schools(&pool).await;
+        let term_id = current_term(&pool, &south).await;
+        let today = chrono::Utc::now().date_naive();
+        let earlier_term_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO terms (academic_year_id, name, starts_on, ends_on) SELECT academic_year_id, 'Earlier', $2, $3 FROM terms WHERE id = $1 RETURNING id")
+            .bind(term_id)
+            .bind(today - chrono::Days::new(60))
+            .bind(today - chrono::Days::new(31))
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let physics_id: Uuid = sqlx::query_scalar(r#"INSERT INTO subjects (code, names) VALUES ('PHYS', '{"en": "Physics"}') RETURNING id"#)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let teacher = insert_user(&pool, "maths@example.com", &[Role::Teacher], Some(south.school_id)).await;
+        teach(&pool, &south, &teacher, south.subject_id, term_id).await;
+        // Physics was on the class's curriculum last term only
+        teach(&pool, &south, &teacher, physics_id, earlier_term_id).await;
+
+        assert!(grade(&pool, &teacher, south.student_id, south.subject_id, None).await.is_ok());
+        assert_eq!(status(grade(&pool, &teacher, south.student_id, physics_id, None).await), StatusCode::FORBIDDEN);
+        let physics_grades: i64 = sqlx::query_scalar("SELECT count(*) FROM grades WHERE subject_id = $1")
+            .bind(physics_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(physics_grades, 0);
+    }
+
+    #[sqlx::test]
+    async fn staff_grades_on_a_teachers_behalf_are_recorded(pool: PgPool) {
+        let (_, south) = two_schools(&pool).await;
+        let term_id = current_term(&pool, &south).await;
+        let teacher = insert_user(&pool, "maths@example.com", &[Role::Teacher], Some(south.school_id)).await;
+        let colleague = insert_user(&pool, "colleague@example.com", &[Role::Teacher], Some(south.school_id)).await;
+        teach(&pool, &south, &teacher, south.subject_id, term_id).await;
+        qualify(&pool, &colleague, south.subject_id).await;
+
+        let entered = grade(&pool, &south.director, south.student_id, south.subject_id, Some(teacher.id)).await.unwrap();
+        assert_eq!((entered.teacher_id, entered.entered_by), (teacher.id, Some(south.director.id)));
+        let own = grade(&pool, &teacher, south.student_id, south.subject_id, Some(teacher.id)).await.unwrap();
+        assert_eq!((own.teacher_id, own.entered_by), (teacher.id, None));
+
+        // Only staff enter grades for someone else, and only for teachers
+        assert_eq!(status(grade(&pool, &teacher, south.student_id, south.subject_id, Some(colleague.id)).await), StatusCode::FORBIDDEN);
+        assert_eq!(status(grade(&pool, &south.parent, south.student_id, south.subject_id, Some(teacher.id)).await), StatusCode::FORBIDDEN);
+        assert_eq!(status(grade(&pool, &south.director, south.student_id, south.subject_id, Some(south.parent.id)).await), StatusCode::BAD_REQUEST);
+        assert_eq!(status(grade(&pool, &south.director, south.student_id, south.subject_id, None).await), StatusCode::BAD_REQUEST);
+    }
+
+    // === Terms ===
+    #[sqlx::test]
+    async fn editing_a_term_leaves_other_terms_records_alone(pool: PgPool) {
+        let (_, south) = two_schools(&pool).await;
+        let year_id: Uuid = sqlx::query_scalar("SELECT id FROM academic_years WHERE school_id = $1")
+            .bind(south.school_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let date = |value: &str| value.parse::<chrono::NaiveDate>().unwrap();
+        let new_term = |name: &str, starts_on: &str, ends_on: &str| {
+            let input = NewTerm { academic_year_id: year_id, name: name.to_string(), starts_on: date(starts_on), ends_on: date(ends_on) };
+            create_term(State(pool.clone()), south.director.clone(), Json(input))
+        };
+        let absence_on = |day: &str| {
+            sqlx::query_scalar::<_, Uuid>("INSERT INTO absences (student_id, date) VALUES ($1, $2) RETURNING id")
+                .bind(south.student_id)
+                .bind(date(day))
+                .fetch_one(&pool)
+        };
+        let term_of = |absence_id: Uuid| {
+            sqlx::query_scalar::<_, Option<Uuid>>("SELECT term_id FROM absences WHERE id = $1")
+                .bind(absence_id)
+                .fetch_one(&pool)
+        };
+
+        let early_december = absence_on("2026-12-15").await.unwrap();
+        let march = absence_on("2027-03-01").await.unwrap();
+        let (_, Json(autumn)) = new_term("Autumn", "2026-09-15", "2026-12-31").await.unwrap();
+        let (_, Json(spring)) = new_term("Spring", "2027-01-01", "2027-06-30").await.unwrap();
+        assert_eq!(term_of(early_december).await.unwrap(), Some(autumn.id));
+        assert_eq!(term_of(march).await.unwrap(), Some(spring.id));
+
+        // Taken out of its term by hand: only a change of the spring term may touch it
+        sqlx::query("UPDATE absences SET term_id = NULL WHERE id = $1").bind(march).execute(&pool).await.unwrap();
+
+        let input = UpdateTerm { name: None, starts_on: None, ends_on: Some(date("2026-11-30")) };
+        let Json(autumn) = update_term(State(pool.clone()), south.director.clone(), Path(autumn.id), Json(input)).await.unwrap();
+        assert_eq!(autumn.ends_on, date("2026-11-30"));
+        assert_eq!(term_of(early_december).await.unwrap(), None);
+        assert_eq!(term_of(march).await.unwrap(), None);
+
+        let input = UpdateTerm { name: None, starts_on: Some(date("2026-12-01")), ends_on: None };
+        let Json(spring) = update_term(State(pool.clone()), south.director.clone(), Path(spring.id), Json(input)).await.unwrap();
+        assert_eq!(spring.starts_on, date("2026-12-01"));
+        assert_eq!(term_of(early_december).await.unwrap(), Some(spring.id));
+        assert_eq!(term_of(march).await.unwrap(), Some(spring.id));
+    }
+}
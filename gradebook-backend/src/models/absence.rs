@@ -9,6 +9,8 @@ pub struct Absence {
     pub student_id: Uuid,
     pub date: NaiveDate,
     pub reason: Option<String>,
+    // The term the record falls in, if one is set up for that day
+    pub term_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
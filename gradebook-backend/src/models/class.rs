@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Class {
+    pub id: Uuid,
+    pub school_id: Uuid,
+    pub grade_level: i16,
+    // The parallel within the grade level, upper case: "A", "B", ...
+    pub letter: String,
+    // An academic year of the class's school
+    pub academic_year_id: Uuid,
+    pub homeroom_teacher_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewClass {
+    // Defaults to the school of the user creating the class
+    pub school_id: Option<Uuid>,
+    pub grade_level: i16,
+    pub letter: String,
+    pub academic_year_id: Uuid,
+    pub homeroom_teacher_id: Option<Uuid>,
+}
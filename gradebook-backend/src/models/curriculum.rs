@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+// A subject a class has in a term, and who teaches it
+#[derive(Debug, Serialize, FromRow)]
+pub struct CurriculumEntry {
+    pub id: Uuid,
+    pub class_id: Uuid,
+    pub term_id: Uuid,
+    pub subject_id: Uuid,
+    // None for entries carried over from the former teacher assignments
+    pub weekly_hours: Option<i16>,
+    pub teacher_ids: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewCurriculumEntry {
+    pub class_id: Uuid,
+    pub term_id: Uuid,
+    pub subject_id: Uuid,
+    pub weekly_hours: i16,
+    // May be empty while nobody has been found to teach the subject
+    #[serde(default)]
+    pub teacher_ids: Vec<Uuid>,
+}
+
+// Copies the curriculum of one term into another of the same academic year
+#[derive(Debug, Deserialize)]
+pub struct CopyCurriculum {
+    pub from_term_id: Uuid,
+    pub to_term_id: Uuid,
+    // Only this class instead of all of them
+    pub class_id: Option<Uuid>,
+}
@@ -7,16 +7,21 @@ use chrono::{DateTime, Utc};
 pub struct Grade {
     pub id: Uuid,
     pub student_id: Uuid,
-    pub subject: String,
+    pub subject_id: Uuid,
     pub value: i16,
     pub teacher_id: Uuid,
+    // Admin or director who entered the grade on the teacher's behalf
+    pub entered_by: Option<Uuid>,
+    // The term the record falls in, if one is set up for that day
+    pub term_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct NewGrade {
     pub student_id: Uuid,
-    pub subject: String,
+    pub subject_id: Uuid,
     pub value: i16,
-    pub teacher_id: Uuid,
+    // Teachers always grade in their own name; admins and directors must name the teacher
+    pub teacher_id: Option<Uuid>,
 }
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use super::user::Role;
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Invite {
+    pub id: Uuid,
+    pub role: Role,
+    pub student_id: Option<Uuid>,
+    // School the new account joins
+    pub school_id: Option<Uuid>,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub redeemed_at: Option<DateTime<Utc>>,
+    pub redeemed_by: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewInvite {
+    pub role: Role,
+    // Only for parent invites: the child the new account gets linked to
+    pub student_id: Option<Uuid>,
+    // Defaults to the student's school for parent invites, otherwise the inviter's
+    pub school_id: Option<Uuid>,
+    pub expires_in_days: Option<i64>,
+}
+
+// The plaintext code is only ever returned here, right after creation.
+#[derive(Debug, Serialize)]
+pub struct CreatedInvite {
+    #[serde(flatten)]
+    pub invite: Invite,
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Registration {
+    pub code: String,
+    pub email: String,
+    pub password: String,
+    pub first_name: String,
+    pub last_name: String,
+}
@@ -2,4 +2,12 @@ pub mod user;
 pub mod student;
 pub mod grade;
 pub mod parent_student;
-pub mod absence;
\ No newline at end of file
+pub mod absence;
+pub mod invite;
+pub mod api_key;
+pub mod curriculum;
+pub mod school;
+pub mod class;
+pub mod subject;
+pub mod teacher;
+pub mod term;
\ No newline at end of file
@@ -7,16 +7,25 @@ use chrono::{DateTime, Utc};
 pub struct Student {
     pub id: Uuid,
     pub user_id: Uuid,
-    pub class: String,
+    pub class_id: Option<Uuid>,
+    pub school_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
+    // Name of the class, e.g. "10A" (when joined)
+    #[sqlx(default)]
+    pub class: Option<String>,
     // User information fields (when joined)
+    #[sqlx(default)]
     pub first_name: Option<String>,
+    #[sqlx(default)]
     pub last_name: Option<String>,
+    #[sqlx(default)]
     pub email: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct NewStudent {
     pub user_id: Uuid,
-    pub class: String,
+    pub class_id: Option<Uuid>,
+    // Defaults to the school of the student's user account
+    pub school_id: Option<Uuid>,
 }
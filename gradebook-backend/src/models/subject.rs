@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Subject {
+    pub id: Uuid,
+    // Short, stable identifier such as "MATH"
+    pub code: String,
+    // Name per language code: {"en": "Mathematics", "bg": "Математика"}
+    pub names: Json<BTreeMap<String, String>>,
+    // Inactive subjects keep their grades but can't be given new ones
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewSubject {
+    pub code: String,
+    pub names: BTreeMap<String, String>,
+    pub active: Option<bool>,
+}
+
+// Folds the given subjects into the one merged into: their grades and
+// curriculum entries move over and they are deleted.
+#[derive(Debug, Deserialize)]
+pub struct MergeSubjects {
+    pub subject_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergedSubject {
+    #[serde(flatten)]
+    pub subject: Subject,
+    pub grades_moved: u64,
+    pub curriculum_moved: u64,
+}
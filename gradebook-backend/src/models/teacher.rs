@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use chrono::NaiveDate;
+
+// A user with the teacher role together with their profile
+#[derive(Debug, Serialize, FromRow)]
+pub struct TeacherProfile {
+    pub user_id: Uuid,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub school_id: Option<Uuid>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub date_of_birth: Option<NaiveDate>,
+    pub hired_on: Option<NaiveDate>,
+    // Subjects the teacher is qualified to teach, by subject code
+    pub subject_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTeacherProfile {
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub date_of_birth: Option<NaiveDate>,
+    pub hired_on: Option<NaiveDate>,
+}
+
+// Replaces all qualifications of a teacher
+#[derive(Debug, Deserialize)]
+pub struct TeacherQualifications {
+    pub subject_ids: Vec<Uuid>,
+}
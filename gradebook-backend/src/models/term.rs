@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use chrono::{DateTime, NaiveDate, Utc};
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct AcademicYear {
+    pub id: Uuid,
+    pub school_id: Uuid,
+    // e.g. "2025/2026"
+    pub name: String,
+    pub starts_on: NaiveDate,
+    pub ends_on: NaiveDate,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewAcademicYear {
+    // Defaults to the school of the user creating the year
+    pub school_id: Option<Uuid>,
+    pub name: String,
+    pub starts_on: NaiveDate,
+    pub ends_on: NaiveDate,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Term {
+    pub id: Uuid,
+    pub academic_year_id: Uuid,
+    // e.g. "First term"
+    pub name: String,
+    // Both days belong to the term
+    pub starts_on: NaiveDate,
+    pub ends_on: NaiveDate,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewTerm {
+    pub academic_year_id: Uuid,
+    pub name: String,
+    pub starts_on: NaiveDate,
+    pub ends_on: NaiveDate,
+}
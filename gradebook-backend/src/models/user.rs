@@ -3,27 +3,36 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+// Deliberately has no `hashed_password`: the hash stays in the database and
+// can never end up in a response.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct User {
     pub id: Uuid,
     pub email: String,
-    pub hashed_password: String,
-    pub role: Role,
+    // Never empty; kept in `Role` order, so the first one is the most privileged
+    pub roles: Vec<Role>,
     pub first_name: String,
     pub last_name: String,
     pub created_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+    // Who checks the password at login: "local" or a directory such as "ldap"
+    pub auth_provider: String,
+    // None for accounts that aren't tied to a school, such as admins
+    pub school_id: Option<Uuid>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct NewUser {
     pub email: String,
     pub password: String,
-    pub role: Role,
+    pub roles: Vec<Role>,
     pub first_name: String,
     pub last_name: String,
+    pub school_id: Option<Uuid>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+// Declared from most to least privileged; role sets are sorted in this order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
 #[sqlx(type_name = "text", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum Role {
@@ -34,3 +43,34 @@ pub enum Role {
     Student,
 }
 
+// `users.roles` is a TEXT[]
+impl sqlx::postgres::PgHasArrayType for Role {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        <String as sqlx::postgres::PgHasArrayType>::array_type_info()
+    }
+}
+
+impl Role {
+    // Sorted and without duplicates, as stored in `users.roles`
+    pub fn normalize(mut roles: Vec<Role>) -> Vec<Role> {
+        roles.sort();
+        roles.dedup();
+        roles
+    }
+}
+
+impl User {
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.contains(&role)
+    }
+
+    pub fn has_any_role(&self, roles: &[Role]) -> bool {
+        self.roles.iter().any(|role| roles.contains(role))
+    }
+
+    // The most privileged role, shown where a single role is expected
+    pub fn primary_role(&self) -> Role {
+        self.roles.first().cloned().unwrap_or(Role::Student)
+    }
+}
+
@@ -0,0 +1,196 @@
+use std::sync::OnceLock;
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// Passwords are hashed here with argon2id and never sent to the database in
+// plaintext. Hashes created earlier by pgcrypto's crypt(..., gen_salt('bf'))
+// are bcrypt; they still verify and get replaced with argon2id on the next
+// successful login.
+
+#[derive(Debug, thiserror::Error)]
+pub enum PasswordError {
+    #[error("password hashing failed: {0}")]
+    Hash(String),
+    #[error("password hashing task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+    #[error(transparent)]
+    Db(#[from] sqlx::Error),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verification {
+    Invalid,
+    Valid,
+    // Correct password, but stored with a legacy algorithm or outdated parameters
+    ValidNeedsRehash,
+}
+
+fn argon2() -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default())
+}
+
+fn is_bcrypt(stored: &str) -> bool {
+    stored.starts_with("$2a$") || stored.starts_with("$2b$") || stored.starts_with("$2y$")
+}
+
+fn hash_blocking(password: &str) -> Result<String, PasswordError> {
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    argon2()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| PasswordError::Hash(e.to_string()))
+}
+
+fn verify_blocking(password: &str, stored: &str) -> Verification {
+    if is_bcrypt(stored) {
+        return match bcrypt::verify(password, stored) {
+            Ok(true) => Verification::ValidNeedsRehash,
+            _ => Verification::Invalid,
+        };
+    }
+
+    let Ok(parsed) = PasswordHash::new(stored) else {
+        return Verification::Invalid;
+    };
+    if argon2().verify_password(password.as_bytes(), &parsed).is_err() {
+        return Verification::Invalid;
+    }
+
+    let current = Params::default();
+    let outdated = parsed.algorithm != argon2::ARGON2ID_IDENT
+        || Params::try_from(&parsed).map_or(true, |params| {
+            params.m_cost() != current.m_cost()
+                || params.t_cost() != current.t_cost()
+                || params.p_cost() != current.p_cost()
+        });
+    if outdated {
+        Verification::ValidNeedsRehash
+    } else {
+        Verification::Valid
+    }
+}
+
+// Hash of a random password, verified against when the account does not exist
+// so that unknown emails take as long to reject as wrong passwords.
+fn dummy_hash() -> &'static str {
+    static DUMMY: OnceLock<String> = OnceLock::new();
+    DUMMY.get_or_init(|| hash_blocking(&crate::auth::generate_token()).unwrap_or_default())
+}
+
+pub async fn hash(password: &str) -> Result<String, PasswordError> {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || hash_blocking(&password)).await?
+}
+
+pub async fn verify(password: &str, stored: &str) -> Result<Verification, PasswordError> {
+    let (password, stored) = (password.to_string(), stored.to_string());
+    Ok(tokio::task::spawn_blocking(move || verify_blocking(&password, &stored)).await?)
+}
+
+// Burns the same time as a real verification; always fails.
+pub async fn verify_nothing(password: &str) -> Result<(), PasswordError> {
+    verify(password, dummy_hash()).await.map(|_| ())
+}
+
+// Checks a user's password and transparently upgrades the stored hash when needed.
+pub async fn verify_user(pool: &PgPool, user_id: Uuid, password: &str) -> Result<bool, PasswordError> {
+    let stored: Option<String> = sqlx::query_scalar("SELECT hashed_password FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    let Some(stored) = stored else {
+        verify_nothing(password).await?;
+        return Ok(false);
+    };
+
+    match verify(password, &stored).await? {
+        Verification::Invalid => Ok(false),
+        Verification::Valid => Ok(true),
+        Verification::ValidNeedsRehash => {
+            let upgraded = hash(password).await?;
+            // Only replace the hash we verified, in case the password changed meanwhile
+            sqlx::query("UPDATE users SET hashed_password = $1 WHERE id = $2 AND hashed_password = $3")
+                .bind(upgraded)
+                .bind(user_id)
+                .bind(&stored)
+                .execute(pool)
+                .await?;
+            tracing::info!(%user_id, "upgraded password hash to argon2id");
+            Ok(true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::Role;
+    use crate::test_support::insert_user;
+
+    const PASSWORD: &str = "correct horse battery";
+
+    // A legacy hash as pgcrypto's crypt(..., gen_salt('bf')) made them
+    fn bcrypt_hash(password: &str) -> String {
+        bcrypt::hash(password, 4).unwrap()
+    }
+
+    #[tokio::test]
+    async fn argon2_hashes_verify() {
+        let stored = hash(PASSWORD).await.unwrap();
+        assert!(stored.starts_with("$argon2id$"));
+        assert_eq!(verify(PASSWORD, &stored).await.unwrap(), Verification::Valid);
+    }
+
+    #[tokio::test]
+    async fn bcrypt_hashes_verify_and_need_a_rehash() {
+        let stored = bcrypt_hash(PASSWORD);
+        assert_eq!(verify(PASSWORD, &stored).await.unwrap(), Verification::ValidNeedsRehash);
+    }
+
+    #[tokio::test]
+    async fn outdated_argon2_parameters_need_a_rehash() {
+        let salt = SaltString::generate(&mut rand::rngs::OsRng);
+        let weak = Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::new(8, 1, 1, None).unwrap());
+        let stored = weak.hash_password(PASSWORD.as_bytes(), &salt).unwrap().to_string();
+        assert_eq!(verify(PASSWORD, &stored).await.unwrap(), Verification::ValidNeedsRehash);
+    }
+
+    #[tokio::test]
+    async fn wrong_passwords_are_rejected() {
+        for stored in [hash(PASSWORD).await.unwrap(), bcrypt_hash(PASSWORD)] {
+            assert_eq!(verify("wrong horse battery", &stored).await.unwrap(), Verification::Invalid, "{}", stored);
+        }
+    }
+
+    #[tokio::test]
+    async fn malformed_hashes_are_rejected() {
+        for stored in ["", "!directory", "$argon2id$v=19$garbage", "$2b$12$short", "plaintext"] {
+            assert_eq!(verify(PASSWORD, stored).await.unwrap(), Verification::Invalid, "{}", stored);
+        }
+        assert!(verify_nothing(PASSWORD).await.is_ok());
+    }
+
+    #[sqlx::test]
+    async fn legacy_hashes_are_upgraded_on_login(pool: PgPool) {
+        let user = insert_user(&pool, "a@example.com", &[Role::Teacher], None).await;
+        sqlx::query("UPDATE users SET hashed_password = $1 WHERE id = $2")
+            .bind(bcrypt_hash(PASSWORD))
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(!verify_user(&pool, user.id, "wrong horse battery").await.unwrap());
+        assert!(verify_user(&pool, user.id, PASSWORD).await.unwrap());
+        let stored: String = sqlx::query_scalar("SELECT hashed_password FROM users WHERE id = $1")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(verify(PASSWORD, &stored).await.unwrap(), Verification::Valid);
+        assert!(!verify_user(&pool, Uuid::new_v4(), PASSWORD).await.unwrap());
+    }
+}
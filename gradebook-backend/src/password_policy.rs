@@ -0,0 +1,319 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::password::{self, PasswordError, Verification};
+
+// Bundled list of common and breached passwords; PASSWORD_BLOCKLIST_FILE can add more.
+const BUNDLED_BLOCKLIST: &str = include_str!("../data/common-passwords.txt");
+
+// Rules every new password has to satisfy: create_user, update_user, /me,
+// password reset and registration all go through `PasswordPolicy::check`.
+#[derive(Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    // How many of lowercase, uppercase, digits and symbols must appear (0-4)
+    pub min_char_classes: usize,
+    // Number of most recent passwords (including the current one) that cannot be reused
+    pub history: i64,
+    blocklist: Arc<HashSet<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PolicyViolation {
+    pub code: &'static str,
+    pub message: String,
+}
+
+// Rejected password, returned as 422 with every violated rule listed.
+#[derive(Debug)]
+pub struct PolicyRejection(pub Vec<PolicyViolation>);
+
+#[derive(Serialize)]
+struct RejectionBody<'a> {
+    error: &'static str,
+    violations: &'a [PolicyViolation],
+}
+
+impl IntoResponse for PolicyRejection {
+    fn into_response(self) -> Response {
+        let body = RejectionBody {
+            error: "Password does not meet the password policy",
+            violations: &self.0,
+        };
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response()
+    }
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+fn parse_blocklist(contents: &str, into: &mut HashSet<String>) {
+    into.extend(
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_lowercase),
+    );
+}
+
+fn char_classes(password: &str) -> usize {
+    let has = |check: fn(char) -> bool| password.chars().any(check);
+    [
+        has(char::is_lowercase),
+        has(char::is_uppercase),
+        has(|c| c.is_ascii_digit()),
+        has(|c| !c.is_alphanumeric()),
+    ]
+    .into_iter()
+    .filter(|present| *present)
+    .count()
+}
+
+// Lower-case pieces of an account's email and names that a password may not
+// contain: "jane.doe@school.org" gives "jane.doe", "jane" and "doe". Pieces
+// shorter than 3 characters are too common to rule out.
+fn personal_words(identity: &[&str]) -> Vec<String> {
+    let mut words = Vec::new();
+    for item in identity {
+        let item = item.trim().to_lowercase();
+        let item = item.split('@').next().unwrap_or_default();
+        words.push(item.to_string());
+        words.extend(item.split(|c: char| !c.is_alphanumeric()).map(str::to_string));
+    }
+    words.retain(|word| word.chars().count() >= 3);
+    words
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> Self {
+        let mut blocklist = HashSet::new();
+        parse_blocklist(BUNDLED_BLOCKLIST, &mut blocklist);
+        if let Ok(path) = std::env::var("PASSWORD_BLOCKLIST_FILE") {
+            let contents = std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("PASSWORD_BLOCKLIST_FILE {} could not be read: {}", path, e));
+            parse_blocklist(&contents, &mut blocklist);
+        }
+
+        PasswordPolicy {
+            min_length: env_or("PASSWORD_MIN_LENGTH", 10),
+            min_char_classes: env_or("PASSWORD_MIN_CHAR_CLASSES", 2usize).min(4),
+            history: env_or("PASSWORD_HISTORY", 5),
+            blocklist: Arc::new(blocklist),
+        }
+    }
+
+    // Also catches listed passwords with digits or symbols tacked on ("Password123!").
+    fn is_blocklisted(&self, password: &str) -> bool {
+        let lowered = password.to_lowercase();
+        let stem = lowered.trim_end_matches(|c: char| !c.is_alphabetic());
+        self.blocklist.contains(&lowered) || (stem.chars().count() >= 4 && self.blocklist.contains(stem))
+    }
+
+    // Rules that don't need the database; `identity` holds the email and names of
+    // the account. All violations are reported, not just the first.
+    pub fn violations(&self, new_password: &str, identity: &[&str]) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+
+        if new_password.chars().count() < self.min_length {
+            violations.push(PolicyViolation {
+                code: "too_short",
+                message: format!("Password must be at least {} characters long", self.min_length),
+            });
+        }
+        if char_classes(new_password) < self.min_char_classes {
+            violations.push(PolicyViolation {
+                code: "too_few_character_classes",
+                message: format!(
+                    "Password must mix at least {} of: lowercase letters, uppercase letters, digits, symbols",
+                    self.min_char_classes
+                ),
+            });
+        }
+        if self.is_blocklisted(new_password) {
+            violations.push(PolicyViolation {
+                code: "common_password",
+                message: "Password is too common or has appeared in a data breach".to_string(),
+            });
+        }
+        let lowered = new_password.to_lowercase();
+        if personal_words(identity).iter().any(|word| lowered.contains(word.as_str())) {
+            violations.push(PolicyViolation {
+                code: "contains_personal_info",
+                message: "Password must not contain your email address or name".to_string(),
+            });
+        }
+
+        violations
+    }
+
+    // Full check for a password about to be set, with the email and names the
+    // account is getting. Pass the user id when changing an existing user's password
+    // so its current email and names and recent passwords are rejected too.
+    pub async fn check(&self, pool: &PgPool, user_id: Option<Uuid>, new_password: &str, identity: &[&str])
+        -> Result<Result<(), PolicyRejection>, PasswordError>
+    {
+        let current: Option<(String, String, String)> = match user_id {
+            Some(user_id) => sqlx::query_as("SELECT email, first_name, last_name FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await?,
+            None => None,
+        };
+        let mut identity = identity.to_vec();
+        if let Some((email, first_name, last_name)) = &current {
+            identity.extend([email.as_str(), first_name.as_str(), last_name.as_str()]);
+        }
+        let mut violations = self.violations(new_password, &identity);
+
+        if let Some(user_id) = user_id {
+            if self.was_used_recently(pool, user_id, new_password).await? {
+                violations.push(PolicyViolation {
+                    code: "recently_used",
+                    message: format!("Password must differ from the last {} passwords", self.history),
+                });
+            }
+        }
+
+        Ok(if violations.is_empty() { Ok(()) } else { Err(PolicyRejection(violations)) })
+    }
+
+    async fn was_used_recently(&self, pool: &PgPool, user_id: Uuid, new_password: &str) -> Result<bool, PasswordError> {
+        if self.history <= 0 {
+            return Ok(false);
+        }
+        let recent: Vec<String> = sqlx::query_scalar(
+            "SELECT hashed_password FROM password_history WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2"
+        )
+        .bind(user_id)
+        .bind(self.history)
+        .fetch_all(pool)
+        .await?;
+
+        for hashed in &recent {
+            if password::verify(new_password, hashed).await? != Verification::Invalid {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    // Records a newly set password and forgets the ones beyond the history length.
+    pub async fn remember(&self, conn: &mut PgConnection, user_id: Uuid, hashed_password: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO password_history (user_id, hashed_password) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(hashed_password)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query(
+            r#"
+            DELETE FROM password_history
+            WHERE user_id = $1 AND id NOT IN (
+                SELECT id FROM password_history WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2
+            )
+            "#
+        )
+        .bind(user_id)
+        .bind(self.history.max(0))
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::Role;
+    use crate::test_support::insert_user;
+
+    const JANE: &[&str] = &["jane.doe@school.org", "Jane", "Doe"];
+
+    fn policy(history: i64) -> PasswordPolicy {
+        let mut blocklist = HashSet::new();
+        parse_blocklist("# common\npassword\nDragon\n\nqwerty\n", &mut blocklist);
+        PasswordPolicy { min_length: 10, min_char_classes: 3, history, blocklist: Arc::new(blocklist) }
+    }
+
+    fn codes(violations: &[PolicyViolation]) -> Vec<&'static str> {
+        violations.iter().map(|violation| violation.code).collect()
+    }
+
+    #[test]
+    fn char_classes_are_counted() {
+        for (password, expected) in [
+            ("", 0),
+            ("abc", 1),
+            ("ABC", 1),
+            ("123", 1),
+            ("!? ", 1),
+            ("abcDEF", 2),
+            ("abcDEF123", 3),
+            ("abcDEF123!", 4),
+            ("жабаЖАБА", 2),
+        ] {
+            assert_eq!(char_classes(password), expected, "{}", password);
+        }
+    }
+
+    #[test]
+    fn violations_follow_the_rules() {
+        let policy = policy(5);
+        for (password, expected) in [
+            ("Tr0ub4dor&3x", vec![]),
+            ("Sh0rt!", vec!["too_short"]),
+            ("alllowercaseletters", vec!["too_few_character_classes"]),
+            ("lowercase and digits 123", vec![]),
+            ("x", vec!["too_short", "too_few_character_classes"]),
+            // Listed passwords in any case, and with digits or symbols tacked on
+            ("Password", vec!["too_short", "too_few_character_classes", "common_password"]),
+            ("PASSWORD12345!", vec!["common_password"]),
+            ("dragon2024!!", vec!["common_password"]),
+            ("Qwerty!!!!12", vec!["common_password"]),
+            // Only a stem of four letters or more counts
+            ("Passwords123!", vec![]),
+            // The email and names of the account
+            ("Jane.Doe@2024", vec!["contains_personal_info"]),
+            ("MyNameIsJANE1!", vec!["contains_personal_info"]),
+            ("doe-a-deer-42!", vec!["contains_personal_info"]),
+        ] {
+            assert_eq!(codes(&policy.violations(password, JANE)), expected, "{}", password);
+        }
+    }
+
+    #[test]
+    fn short_personal_words_are_ignored() {
+        let policy = policy(5);
+        assert!(policy.violations("Al-Jo 12345!", &["al@example.com", "Al", "Jo"]).is_empty());
+        assert!(policy.violations("Jane.Doe@2024", &[]).is_empty());
+    }
+
+    #[sqlx::test]
+    async fn recent_passwords_cannot_be_reused(pool: PgPool) {
+        let policy = policy(2);
+        let user = insert_user(&pool, "a@example.com", &[Role::Teacher], None).await;
+        let mut conn = pool.acquire().await.unwrap();
+        for old in ["First pass 111!", "Second pass 222!", "Third pass 333!"] {
+            policy.remember(&mut conn, user.id, &password::hash(old).await.unwrap()).await.unwrap();
+        }
+
+        for (password, reused) in [("Third pass 333!", true), ("Second pass 222!", true), ("First pass 111!", false), ("Fourth pass 444!", false)] {
+            let rejection = policy.check(&pool, Some(user.id), password, &[]).await.unwrap().err();
+            let codes = rejection.map(|rejection| codes(&rejection.0)).unwrap_or_default();
+            assert_eq!(codes.contains(&"recently_used"), reused, "{}", password);
+        }
+
+        // New accounts have no history, but their stored email and names are checked
+        assert!(policy.check(&pool, None, "Third pass 333!", &[]).await.unwrap().is_ok());
+        let rejection = policy.check(&pool, Some(user.id), "Testing pass 5!", &[]).await.unwrap().unwrap_err();
+        assert_eq!(codes(&rejection.0), vec!["contains_personal_info"]);
+    }
+}
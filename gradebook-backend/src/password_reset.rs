@@ -0,0 +1,158 @@
+use chrono::{Duration, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::auth::{generate_token, hash_token};
+use crate::mailer::{Email, MailError, Mailer};
+
+#[derive(Clone)]
+pub struct ResetConfig {
+    pub ttl: Duration,
+    // Frontend page the emailed link points to; the token is appended as `?token=`
+    pub link_base: String,
+}
+
+impl ResetConfig {
+    pub fn from_env() -> Self {
+        let ttl_minutes = std::env::var("PASSWORD_RESET_TTL_MINUTES")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(30);
+        let link_base = std::env::var("PASSWORD_RESET_URL")
+            .unwrap_or_else(|_| "http://localhost:5173/reset-password".to_string());
+        ResetConfig { ttl: Duration::minutes(ttl_minutes), link_base }
+    }
+
+    pub fn link(&self, token: &str) -> String {
+        format!("{}?token={}", self.link_base, token)
+    }
+}
+
+// Issues a new reset token for the user, invalidating any earlier unused ones.
+// Returns the plaintext token; only its hash is stored.
+pub async fn issue(pool: &PgPool, user_id: Uuid, ttl: Duration) -> Result<String, sqlx::Error> {
+    let token = generate_token();
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE password_resets SET used_at = now() WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("INSERT INTO password_resets (user_id, token_hash, expires_at) VALUES ($1, $2, $3)")
+        .bind(user_id)
+        .bind(hash_token(&token))
+        .bind(Utc::now() + ttl)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(token)
+}
+
+// Marks a valid token as used and returns the user it belongs to. A token works
+// exactly once and never after it expires.
+pub async fn consume<'e>(db: impl PgExecutor<'e>, token: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        UPDATE password_resets SET used_at = now()
+        WHERE token_hash = $1 AND used_at IS NULL AND expires_at > now()
+        RETURNING user_id
+        "#
+    )
+    .bind(hash_token(token))
+    .fetch_optional(db)
+    .await
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResetError {
+    #[error(transparent)]
+    Db(#[from] sqlx::Error),
+    #[error(transparent)]
+    Mail(#[from] MailError),
+}
+
+// Emails a reset link if the address belongs to an account with a local password;
+// other addresses are silently ignored so callers cannot probe which emails exist.
+pub async fn request(pool: &PgPool, config: &ResetConfig, mailer: &dyn Mailer, email: &str) -> Result<(), ResetError> {
+    // Matched regardless of case, like at login
+    let user: Option<(Uuid, String, String)> = sqlx::query_as(
+        "SELECT id, first_name, email FROM users WHERE lower(email) = lower($1) AND auth_provider = 'local' ORDER BY email = $1 DESC LIMIT 1"
+    )
+    .bind(email)
+    .fetch_optional(pool)
+    .await?;
+    let Some((user_id, first_name, email)) = user else {
+        return Ok(());
+    };
+
+    let token = issue(pool, user_id, config.ttl).await?;
+    mailer
+        .send(Email {
+            to: email,
+            subject: "Reset your Gradebook password".to_string(),
+            body: format!(
+                "Hello {},\n\nUse the link below to choose a new password. It can be used once and expires in {} minutes.\n\n{}\n\nIf you did not ask for a password reset, you can ignore this message.",
+                first_name,
+                config.ttl.num_minutes(),
+                config.link(&token),
+            ),
+        })
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailer::OutboxMailer;
+    use crate::models::user::Role;
+    use crate::test_support::{directory_user, insert_user, outbox_messages};
+
+    #[sqlx::test]
+    async fn token_works_only_once(pool: PgPool) {
+        let user_id = insert_user(&pool, "a@example.com", &[Role::Teacher], None).await.id;
+        let token = issue(&pool, user_id, Duration::minutes(30)).await.unwrap();
+
+        assert_eq!(consume(&pool, &token).await.unwrap(), Some(user_id));
+        assert_eq!(consume(&pool, &token).await.unwrap(), None);
+    }
+
+    #[sqlx::test]
+    async fn expired_token_is_refused(pool: PgPool) {
+        let user_id = insert_user(&pool, "a@example.com", &[Role::Teacher], None).await.id;
+        let token = issue(&pool, user_id, Duration::seconds(-1)).await.unwrap();
+
+        assert_eq!(consume(&pool, &token).await.unwrap(), None);
+    }
+
+    #[sqlx::test]
+    async fn new_token_replaces_earlier_ones(pool: PgPool) {
+        let user_id = insert_user(&pool, "a@example.com", &[Role::Teacher], None).await.id;
+        let first = issue(&pool, user_id, Duration::minutes(30)).await.unwrap();
+        let second = issue(&pool, user_id, Duration::minutes(30)).await.unwrap();
+
+        assert_eq!(consume(&pool, &first).await.unwrap(), None);
+        assert_eq!(consume(&pool, &second).await.unwrap(), Some(user_id));
+    }
+
+    #[sqlx::test]
+    async fn only_local_accounts_get_a_link(pool: PgPool) {
+        insert_user(&pool, "local@example.com", &[Role::Teacher], None).await;
+        directory_user(&pool, "directory@example.com").await;
+        let dir = tempfile::tempdir().unwrap();
+        let mailer = OutboxMailer::new(dir.path(), "Gradebook <no-reply@example.com>");
+        let config = ResetConfig { ttl: Duration::minutes(30), link_base: "http://app/reset".to_string() };
+
+        for email in ["Local@Example.com", "directory@example.com", "nobody@example.com"] {
+            assert!(request(&pool, &config, &mailer, email).await.is_ok(), "{}", email);
+        }
+
+        let messages = outbox_messages(dir.path());
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("To: local@example.com"));
+        let token = messages[0].split("?token=").nth(1).unwrap().split_whitespace().next().unwrap();
+        assert!(consume(&pool, token).await.unwrap().is_some());
+    }
+}
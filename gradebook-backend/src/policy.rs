@@ -0,0 +1,445 @@
+use uuid::Uuid;
+
+use crate::models::user::{Role, User};
+
+// Central authorization rules. Handlers load whatever facts a decision needs
+// (who a student's parents are, who teaches their class, ...) and then ask
+// `can(user, action, resource)`; the rules themselves never touch the database,
+// so each one can be checked with plain values. A user with several roles may do
+// whatever any one of their roles allows.
+//
+// Schools are separate tenants: whatever the roles say, only admins get at the
+// records of a school other than their own (`in_school`).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Create,
+    Read,
+    Update,
+    Delete,
+    // Act as another user ("view as"); see `impersonation`
+    Impersonate,
+}
+
+// A student as seen from the acting user.
+#[derive(Debug, Clone)]
+pub struct StudentFacts {
+    // The student's own user account
+    pub user_id: Uuid,
+    pub school_id: Option<Uuid>,
+    // The acting user is one of the student's linked parents
+    pub is_child_of_actor: bool,
+    // The acting user teaches the student's class by its curriculum (any term,
+    // any subject) or is its homeroom teacher
+    pub taught_by_actor: bool,
+}
+
+// A class as seen from the acting user.
+#[derive(Debug, Clone)]
+pub struct ClassFacts {
+    pub school_id: Uuid,
+    // The acting user teaches the class by its curriculum (any term, any subject)
+    // or is its homeroom teacher
+    pub taught_by_actor: bool,
+}
+
+// A teacher and their profile.
+#[derive(Debug, Clone)]
+pub struct TeacherFacts {
+    pub user_id: Uuid,
+    pub school_id: Option<Uuid>,
+}
+
+// A grade, or one about to be given. For updates that change the subject the
+// handler checks the new subject as well.
+#[derive(Debug, Clone)]
+pub struct GradeFacts {
+    pub student: StudentFacts,
+    // The curriculum has the acting user teach the grade's subject to the
+    // student's class in the grade's term (in any term for grades outside of terms)
+    pub actor_teaches_subject: bool,
+}
+
+// An absence, or one about to be recorded. For updates that change the date the
+// handler checks the new day as well.
+#[derive(Debug, Clone)]
+pub struct AbsenceFacts {
+    pub student: StudentFacts,
+    // The acting user teaches the student's class in the absence's term (in any
+    // term for days outside of terms) or is its homeroom teacher
+    pub actor_teaches_class: bool,
+}
+
+// Plural variants stand for the collection (listing, creating); singular ones
+// for an existing record together with the facts the rules need about it.
+#[derive(Debug)]
+pub enum Resource<'a> {
+    Users,
+    // An existing account with the given roles
+    User(&'a [Role]),
+    Invites,
+    // An invite for a new account with the given role
+    Invite(&'a Role),
+    ApiKeys,
+    ImpersonationLog,
+    Schools,
+    // The subject catalog, shared by all schools; merging counts as an update
+    Subjects,
+    Teachers,
+    Teacher(&'a TeacherFacts),
+    // The subjects a teacher is qualified to teach
+    TeacherQualifications(&'a TeacherFacts),
+    // Curriculum entries: which subjects a class has in a term, and who teaches them
+    Curriculum,
+    // The curriculum of a class
+    ClassCurriculum(&'a ClassFacts),
+    // Academic years together with their terms
+    AcademicYears,
+    // An academic year of the given school; its terms are edited as part of it
+    AcademicYear(Uuid),
+    Classes,
+    Class(&'a ClassFacts),
+    // The students of a class
+    ClassRoster(&'a ClassFacts),
+    Students,
+    Student(&'a StudentFacts),
+    ParentLinks,
+    // The children linked to the given parent
+    ParentLinksOf(Uuid),
+    Grades,
+    Grade(&'a GradeFacts),
+    Absences,
+    Absence(&'a AbsenceFacts),
+    Stats,
+}
+
+impl Resource<'_> {
+    // The school a singular resource belongs to, which decides tenancy
+    fn school_id(&self) -> Option<Option<Uuid>> {
+        match self {
+            Resource::Student(student) => Some(student.school_id),
+            Resource::Grade(grade) => Some(grade.student.school_id),
+            Resource::Absence(absence) => Some(absence.student.school_id),
+            Resource::Class(class) | Resource::ClassRoster(class) | Resource::ClassCurriculum(class) => Some(Some(class.school_id)),
+            Resource::AcademicYear(school_id) => Some(Some(*school_id)),
+            Resource::Teacher(teacher) | Resource::TeacherQualifications(teacher) => Some(teacher.school_id),
+            _ => None,
+        }
+    }
+}
+
+// Whether `user` may reach records of the given school. Admins work across
+// schools; everyone else only within the school of their account, and records
+// without a school are admin-only.
+pub fn in_school(user: &User, school_id: Option<Uuid>) -> bool {
+    is_admin(user) || (school_id.is_some() && school_id == user.school_id)
+}
+
+fn is_admin(user: &User) -> bool {
+    user.has_role(Role::Admin)
+}
+
+fn is_staff(user: &User) -> bool {
+    user.has_any_role(&[Role::Admin, Role::Director])
+}
+
+// Staff see every student, teachers the classes they teach, parents their
+// children and students themselves.
+fn can_read_student(user: &User, student: &StudentFacts) -> bool {
+    user.roles.iter().any(|role| match role {
+        Role::Admin | Role::Director => true,
+        Role::Teacher => student.taught_by_actor,
+        Role::Parent => student.is_child_of_actor,
+        Role::Student => student.user_id == user.id,
+    })
+}
+
+pub fn can(user: &User, action: Action, resource: &Resource) -> bool {
+    use Action::*;
+
+    if resource.school_id().is_some_and(|school_id| !in_school(user, school_id)) {
+        return false;
+    }
+
+    match (resource, action) {
+        (Resource::Users, Read) => is_staff(user),
+        // Account management (create, edit, unlock, sessions, 2FA reset, delete)
+        (Resource::Users, Create | Update | Delete) => is_admin(user),
+        // Admins can't be impersonated: that would be a way around their second factor
+        (Resource::User(roles), Impersonate) => is_admin(user) && !roles.contains(&Role::Admin),
+
+        (Resource::Invites, Read) => is_staff(user),
+        // Directors may invite everyone below them
+        (Resource::Invite(role), Create) => {
+            is_admin(user) || (user.has_role(Role::Director) && !matches!(role, Role::Admin | Role::Director))
+        }
+        (Resource::Invites, Delete) => is_staff(user),
+
+        (Resource::ApiKeys, Create | Read | Delete) => is_admin(user),
+        (Resource::ImpersonationLog, Read) => is_admin(user),
+
+        (Resource::Schools, Create | Update | Delete) => is_admin(user),
+        (Resource::Schools, Read) => true,
+
+        (Resource::Subjects, Create | Update | Delete) => is_admin(user),
+        (Resource::Subjects, Read) => true,
+
+        (Resource::Teachers, Read) => is_staff(user),
+        // Teachers keep their own personal data up to date
+        (Resource::Teacher(teacher), Read | Update) => is_staff(user) || teacher.user_id == user.id,
+        (Resource::TeacherQualifications(_), Update) => is_staff(user),
+
+        // Everyone may see who teaches what in their school
+        (Resource::Curriculum | Resource::ClassCurriculum(_), Read) => true,
+        (Resource::ClassCurriculum(_), Create | Update | Delete) => is_staff(user),
+        // Copying a term's curriculum for all classes at once
+        (Resource::Curriculum, Create) => is_staff(user),
+
+        (Resource::AcademicYears, Create) => is_staff(user),
+        (Resource::AcademicYears | Resource::AcademicYear(_), Read) => true,
+        (Resource::AcademicYear(_), Update | Delete) => is_staff(user),
+
+        (Resource::Classes, Create) => is_staff(user),
+        (Resource::Classes | Resource::Class(_), Read) => true,
+        (Resource::Class(_), Update | Delete) => is_staff(user),
+        (Resource::ClassRoster(class), Read) => is_staff(user) || (user.has_role(Role::Teacher) && class.taught_by_actor),
+
+        (Resource::Students, Create) => is_staff(user),
+        (Resource::Students, Read) => true,
+        (Resource::Student(student), Read) => can_read_student(user, student),
+        (Resource::Student(_), Update | Delete) => is_staff(user),
+
+        (Resource::ParentLinks, Create | Delete) => is_staff(user),
+        (Resource::ParentLinksOf(parent_id), Read) => is_staff(user) || (user.has_role(Role::Parent) && *parent_id == user.id),
+
+        (Resource::Grades, Read) => true,
+        (Resource::Grade(grade), Read) => can_read_student(user, &grade.student),
+        // Teachers grade only the subjects they teach the student's class in the
+        // grade's term. That also decides edits: a colleague's grade can be
+        // changed by a teacher who teaches the same class and subject in that
+        // term, while the author loses access once taken off the curriculum.
+        (Resource::Grade(grade), Create | Update | Delete) => {
+            is_staff(user) || (user.has_role(Role::Teacher) && grade.actor_teaches_subject)
+        }
+
+        (Resource::Absences, Read) => true,
+        (Resource::Absence(absence), Read) => can_read_student(user, &absence.student),
+        (Resource::Absence(absence), Create | Update | Delete) => {
+            is_staff(user) || (user.has_role(Role::Teacher) && absence.actor_teaches_class)
+        }
+
+        // Figures are limited to the students the caller can see (`student_scope`)
+        (Resource::Stats, Read) => true,
+
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    const ACTOR: Uuid = Uuid::from_u128(1);
+    const SOMEONE_ELSE: Uuid = Uuid::from_u128(2);
+    const SCHOOL: Uuid = Uuid::from_u128(10);
+    const OTHER_SCHOOL: Uuid = Uuid::from_u128(11);
+
+    const ROLES: [Role; 5] = [Role::Admin, Role::Director, Role::Teacher, Role::Parent, Role::Student];
+    const ACTIONS: [Action; 5] = [Action::Create, Action::Read, Action::Update, Action::Delete, Action::Impersonate];
+
+    const ALL: &[Role] = &ROLES;
+    const STAFF: &[Role] = &[Role::Admin, Role::Director];
+    const ADMIN: &[Role] = &[Role::Admin];
+    const STAFF_AND_TEACHER: &[Role] = &[Role::Admin, Role::Director, Role::Teacher];
+    const STAFF_AND_PARENT: &[Role] = &[Role::Admin, Role::Director, Role::Parent];
+
+    fn user(roles: &[Role], school_id: Option<Uuid>) -> User {
+        User {
+            id: ACTOR,
+            email: "actor@example.com".to_string(),
+            roles: roles.to_vec(),
+            first_name: "Test".to_string(),
+            last_name: "Actor".to_string(),
+            created_at: Utc::now(),
+            locked_until: None,
+            auth_provider: "local".to_string(),
+            school_id,
+        }
+    }
+
+    // A student the actor is the student, a parent and a teacher of, or none of these
+    fn student(related: bool, school_id: Uuid) -> StudentFacts {
+        StudentFacts {
+            user_id: if related { ACTOR } else { SOMEONE_ELSE },
+            school_id: Some(school_id),
+            is_child_of_actor: related,
+            taught_by_actor: related,
+        }
+    }
+
+    fn class(taught: bool) -> ClassFacts {
+        ClassFacts { school_id: SCHOOL, taught_by_actor: taught }
+    }
+
+    fn teacher(user_id: Uuid) -> TeacherFacts {
+        TeacherFacts { user_id, school_id: Some(SCHOOL) }
+    }
+
+    // Checks every role and action on `resource`: exactly the listed ones are allowed
+    fn check(resource: Resource, allowed: &[(Action, &[Role])]) {
+        for role in ROLES {
+            let user = user(std::slice::from_ref(&role), Some(SCHOOL));
+            for action in ACTIONS {
+                let expected = allowed.iter().any(|(a, roles)| *a == action && roles.contains(&role));
+                assert_eq!(can(&user, action, &resource), expected, "{:?} {:?} {:?}", role, action, resource);
+            }
+        }
+    }
+
+    #[test]
+    fn accounts_and_keys() {
+        use Action::*;
+        check(Resource::Users, &[(Read, STAFF), (Create, ADMIN), (Update, ADMIN), (Delete, ADMIN)]);
+        check(Resource::User(&[Role::Teacher]), &[(Impersonate, ADMIN)]);
+        check(Resource::Invites, &[(Read, STAFF), (Delete, STAFF)]);
+        check(Resource::Invite(&Role::Teacher), &[(Create, STAFF)]);
+        check(Resource::Invite(&Role::Student), &[(Create, STAFF)]);
+        check(Resource::Invite(&Role::Director), &[(Create, ADMIN)]);
+        check(Resource::Invite(&Role::Admin), &[(Create, ADMIN)]);
+        check(Resource::ApiKeys, &[(Create, ADMIN), (Read, ADMIN), (Delete, ADMIN)]);
+        check(Resource::ImpersonationLog, &[(Read, ADMIN)]);
+    }
+
+    #[test]
+    fn schools_subjects_and_teachers() {
+        use Action::*;
+        check(Resource::Schools, &[(Read, ALL), (Create, ADMIN), (Update, ADMIN), (Delete, ADMIN)]);
+        check(Resource::Subjects, &[(Read, ALL), (Create, ADMIN), (Update, ADMIN), (Delete, ADMIN)]);
+        check(Resource::Teachers, &[(Read, STAFF)]);
+        check(Resource::Teacher(&teacher(SOMEONE_ELSE)), &[(Read, STAFF), (Update, STAFF)]);
+        // The actor's own profile, whichever role they act in
+        check(Resource::Teacher(&teacher(ACTOR)), &[(Read, ALL), (Update, ALL)]);
+        check(Resource::TeacherQualifications(&teacher(ACTOR)), &[(Update, STAFF)]);
+    }
+
+    #[test]
+    fn calendar_classes_and_curriculum() {
+        use Action::*;
+        check(Resource::AcademicYears, &[(Read, ALL), (Create, STAFF)]);
+        check(Resource::AcademicYear(SCHOOL), &[(Read, ALL), (Update, STAFF), (Delete, STAFF)]);
+        check(Resource::Classes, &[(Read, ALL), (Create, STAFF)]);
+        check(Resource::Class(&class(true)), &[(Read, ALL), (Update, STAFF), (Delete, STAFF)]);
+        check(Resource::ClassRoster(&class(true)), &[(Read, STAFF_AND_TEACHER)]);
+        check(Resource::ClassRoster(&class(false)), &[(Read, STAFF)]);
+        check(Resource::Curriculum, &[(Read, ALL), (Create, STAFF)]);
+        check(Resource::ClassCurriculum(&class(true)), &[(Read, ALL), (Create, STAFF), (Update, STAFF), (Delete, STAFF)]);
+    }
+
+    #[test]
+    fn students_and_parent_links() {
+        use Action::*;
+        check(Resource::Students, &[(Read, ALL), (Create, STAFF)]);
+        check(Resource::Student(&student(true, SCHOOL)), &[(Read, ALL), (Update, STAFF), (Delete, STAFF)]);
+        check(Resource::Student(&student(false, SCHOOL)), &[(Read, STAFF), (Update, STAFF), (Delete, STAFF)]);
+        check(Resource::ParentLinks, &[(Create, STAFF), (Delete, STAFF)]);
+        check(Resource::ParentLinksOf(ACTOR), &[(Read, STAFF_AND_PARENT)]);
+        check(Resource::ParentLinksOf(SOMEONE_ELSE), &[(Read, STAFF)]);
+    }
+
+    #[test]
+    fn grades_absences_and_stats() {
+        use Action::*;
+        let teaching = GradeFacts { student: student(true, SCHOOL), actor_teaches_subject: true };
+        let other_subject = GradeFacts { student: student(true, SCHOOL), actor_teaches_subject: false };
+        let unrelated = GradeFacts { student: student(false, SCHOOL), actor_teaches_subject: false };
+        check(Resource::Grades, &[(Read, ALL)]);
+        check(Resource::Grade(&teaching), &[(Read, ALL), (Create, STAFF_AND_TEACHER), (Update, STAFF_AND_TEACHER), (Delete, STAFF_AND_TEACHER)]);
+        check(Resource::Grade(&other_subject), &[(Read, ALL), (Create, STAFF), (Update, STAFF), (Delete, STAFF)]);
+        check(Resource::Grade(&unrelated), &[(Read, STAFF), (Create, STAFF), (Update, STAFF), (Delete, STAFF)]);
+
+        let teaching = AbsenceFacts { student: student(true, SCHOOL), actor_teaches_class: true };
+        let other_term = AbsenceFacts { student: student(true, SCHOOL), actor_teaches_class: false };
+        let unrelated = AbsenceFacts { student: student(false, SCHOOL), actor_teaches_class: false };
+        check(Resource::Absences, &[(Read, ALL)]);
+        check(Resource::Absence(&teaching), &[(Read, ALL), (Create, STAFF_AND_TEACHER), (Update, STAFF_AND_TEACHER), (Delete, STAFF_AND_TEACHER)]);
+        check(Resource::Absence(&other_term), &[(Read, ALL), (Create, STAFF), (Update, STAFF), (Delete, STAFF)]);
+        check(Resource::Absence(&unrelated), &[(Read, STAFF), (Create, STAFF), (Update, STAFF), (Delete, STAFF)]);
+
+        check(Resource::Stats, &[(Read, ALL)]);
+    }
+
+    #[test]
+    fn roles_add_up() {
+        let teacher_and_parent = user(&[Role::Teacher, Role::Parent], Some(SCHOOL));
+        // Reads the child's record as a parent, though not their teacher
+        let child = StudentFacts { is_child_of_actor: true, taught_by_actor: false, ..student(false, SCHOOL) };
+        assert!(can(&teacher_and_parent, Action::Read, &Resource::Student(&child)));
+        assert!(!can(&user(&[Role::Teacher], Some(SCHOOL)), Action::Read, &Resource::Student(&child)));
+        // Grades as a teacher what a parent alone couldn't
+        let grade = GradeFacts { student: student(false, SCHOOL), actor_teaches_subject: true };
+        assert!(can(&teacher_and_parent, Action::Create, &Resource::Grade(&grade)));
+        assert!(!can(&user(&[Role::Parent], Some(SCHOOL)), Action::Create, &Resource::Grade(&grade)));
+        // And still can't do what neither role allows
+        assert!(!can(&teacher_and_parent, Action::Read, &Resource::Users));
+        assert!(!can(&teacher_and_parent, Action::Delete, &Resource::Student(&child)));
+    }
+
+    #[test]
+    fn other_schools_are_off_limits() {
+        let foreign_student = student(true, OTHER_SCHOOL);
+        let foreign_grade = GradeFacts { student: student(true, OTHER_SCHOOL), actor_teaches_subject: true };
+        let foreign_class = ClassFacts { school_id: OTHER_SCHOOL, taught_by_actor: true };
+        let foreign_teacher = TeacherFacts { user_id: ACTOR, school_id: Some(OTHER_SCHOOL) };
+        for role in [Role::Director, Role::Teacher, Role::Parent, Role::Student] {
+            let user = user(std::slice::from_ref(&role), Some(SCHOOL));
+            for action in ACTIONS {
+                let resources = [
+                    Resource::Student(&foreign_student),
+                    Resource::Grade(&foreign_grade),
+                    Resource::Class(&foreign_class),
+                    Resource::ClassRoster(&foreign_class),
+                    Resource::ClassCurriculum(&foreign_class),
+                    Resource::AcademicYear(OTHER_SCHOOL),
+                    Resource::Teacher(&foreign_teacher),
+                ];
+                for resource in resources {
+                    assert!(!can(&user, action, &resource), "{:?} {:?} {:?}", role, action, resource);
+                }
+            }
+        }
+        let admin = user(&[Role::Admin], None);
+        assert!(can(&admin, Action::Update, &Resource::Grade(&foreign_grade)));
+        assert!(can(&admin, Action::Read, &Resource::Class(&foreign_class)));
+    }
+
+    #[test]
+    fn in_school_without_a_school() {
+        let director = user(&[Role::Director], Some(SCHOOL));
+        let homeless_director = user(&[Role::Director], None);
+        let admin = user(&[Role::Admin], None);
+
+        assert!(in_school(&director, Some(SCHOOL)));
+        assert!(!in_school(&director, Some(OTHER_SCHOOL)));
+        // Records without a school are for admins only
+        assert!(!in_school(&director, None));
+        // Accounts without a school get at no school's records
+        assert!(!in_school(&homeless_director, Some(SCHOOL)));
+        assert!(!in_school(&homeless_director, None));
+        assert!(in_school(&admin, Some(SCHOOL)));
+        assert!(in_school(&admin, None));
+
+        let own_student = student(true, SCHOOL);
+        assert!(!can(&homeless_director, Action::Read, &Resource::Student(&own_student)));
+    }
+
+    #[test]
+    fn admins_cannot_be_impersonated() {
+        let admin = user(&[Role::Admin], None);
+        assert!(!can(&admin, Action::Impersonate, &Resource::User(&[Role::Admin])));
+        assert!(!can(&admin, Action::Impersonate, &Resource::User(&[Role::Admin, Role::Teacher])));
+        assert!(can(&admin, Action::Impersonate, &Resource::User(&[Role::Director])));
+        assert!(can(&admin, Action::Impersonate, &Resource::User(&[Role::Teacher, Role::Parent])));
+        assert!(!can(&user(&[Role::Director], Some(SCHOOL)), Action::Impersonate, &Resource::User(&[Role::Teacher])));
+    }
+}
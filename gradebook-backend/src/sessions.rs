@@ -0,0 +1,101 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::auth::{generate_token, hash_token};
+
+// A freshly created or rotated session. `refresh_token` is the only copy of the
+// plaintext token; the DB keeps its SHA-256 hash.
+pub struct IssuedSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub refresh_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+pub async fn create<'e>(db: impl PgExecutor<'e>, user_id: Uuid, ttl: Duration) -> Result<IssuedSession, sqlx::Error> {
+    let refresh_token = generate_token();
+    let expires_at = Utc::now() + ttl;
+    let id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO sessions (user_id, refresh_token_hash, expires_at)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        "#
+    )
+    .bind(user_id)
+    .bind(hash_token(&refresh_token))
+    .bind(expires_at)
+    .fetch_one(db)
+    .await?;
+
+    Ok(IssuedSession { id, user_id, refresh_token, expires_at })
+}
+
+// Swaps a valid refresh token for a new one. The old token stops working
+// immediately; the session keeps its original expiry.
+pub async fn rotate<'e>(db: impl PgExecutor<'e>, refresh_token: &str) -> Result<Option<IssuedSession>, sqlx::Error> {
+    let new_token = generate_token();
+    let row: Option<(Uuid, Uuid, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        WITH rotated AS (
+            UPDATE sessions
+            SET refresh_token_hash = $1, last_used_at = now()
+            WHERE refresh_token_hash = $2 AND revoked_at IS NULL AND expires_at > now() AND impersonator_id IS NULL
+            RETURNING id, user_id, expires_at
+        ), retired AS (
+            INSERT INTO retired_refresh_tokens (token_hash, session_id) SELECT $2, id FROM rotated
+        )
+        SELECT id, user_id, expires_at FROM rotated
+        "#
+    )
+    .bind(hash_token(&new_token))
+    .bind(hash_token(refresh_token))
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.map(|(id, user_id, expires_at)| IssuedSession { id, user_id, refresh_token: new_token, expires_at }))
+}
+
+// Revokes the session a refresh token was already rotated out of, since a copy of
+// it is being replayed. Returns the session, if the token was one of those.
+pub async fn revoke_reused<'e>(db: impl PgExecutor<'e>, refresh_token: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        UPDATE sessions SET revoked_at = COALESCE(revoked_at, now())
+        WHERE id = (SELECT session_id FROM retired_refresh_tokens WHERE token_hash = $1)
+        RETURNING id
+        "#
+    )
+    .bind(hash_token(refresh_token))
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn revoke<'e>(db: impl PgExecutor<'e>, session_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE sessions SET revoked_at = now() WHERE id = $1 AND revoked_at IS NULL")
+        .bind(session_id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn revoke_all_for_user<'e>(db: impl PgExecutor<'e>, user_id: Uuid) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("UPDATE sessions SET revoked_at = now() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+// Logs a user out everywhere except the given session (e.g. after they change their own password).
+pub async fn revoke_others<'e>(db: impl PgExecutor<'e>, user_id: Uuid, keep: Uuid) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE sessions SET revoked_at = now() WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(keep)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,71 @@
+use std::path::Path;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::user::{Role, User};
+use crate::password;
+
+// Fixtures shared by the tests of every module.
+
+// Password of the accounts made by `insert_user`
+pub const PASSWORD: &str = "Old password 1";
+
+// An account that only exists in memory, for checks that don't touch the database
+pub fn user(roles: &[Role], school_id: Option<Uuid>) -> User {
+    User {
+        id: Uuid::new_v4(),
+        email: "actor@example.com".to_string(),
+        roles: roles.to_vec(),
+        first_name: "Test".to_string(),
+        last_name: "Actor".to_string(),
+        created_at: chrono::Utc::now(),
+        locked_until: None,
+        auth_provider: "local".to_string(),
+        school_id,
+    }
+}
+
+pub async fn insert_school(pool: &PgPool, name: &str) -> Uuid {
+    sqlx::query_scalar("INSERT INTO schools (name) VALUES ($1) RETURNING id")
+        .bind(name)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+pub async fn insert_user(pool: &PgPool, email: &str, roles: &[Role], school_id: Option<Uuid>) -> User {
+    let hashed_password = password::hash(PASSWORD).await.unwrap();
+    sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users (email, hashed_password, roles, first_name, last_name, school_id)
+        VALUES ($1, $2, $3, 'Test', 'User', $4)
+        RETURNING *
+        "#
+    )
+    .bind(email)
+    .bind(hashed_password)
+    .bind(roles)
+    .bind(school_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+// A teacher whose password the directory checks
+pub async fn directory_user(pool: &PgPool, email: &str) -> User {
+    let user = insert_user(pool, email, &[Role::Teacher], None).await;
+    sqlx::query_as::<_, User>("UPDATE users SET auth_provider = 'ldap', hashed_password = '!directory' WHERE id = $1 RETURNING *")
+        .bind(user.id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+// Messages an `OutboxMailer` wrote to `dir`
+pub fn outbox_messages(dir: &Path) -> Vec<String> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries.map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap()).collect(),
+        Err(_) => Vec::new(),
+    }
+}
@@ -0,0 +1,278 @@
+use chrono::{DateTime, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Serialize;
+use sqlx::PgPool;
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+use crate::auth::hash_token;
+use crate::models::user::{Role, User};
+
+const ISSUER: &str = "Gradebook";
+const DIGITS: usize = 6;
+const STEP: u64 = 30;
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_LEN: usize = 10;
+
+// Whether a second factor is mandatory. When enforced, admins and directors
+// (including users holding either role among others) without TOTP are made to enroll during login.
+#[derive(Clone)]
+pub struct MfaPolicy {
+    pub enforce_privileged: bool,
+}
+
+impl MfaPolicy {
+    pub fn from_env() -> Self {
+        let enforce_privileged = std::env::var("MFA_ENFORCE_PRIVILEGED")
+            .ok()
+            .and_then(|v| v.parse::<bool>().ok())
+            .unwrap_or(false);
+        MfaPolicy { enforce_privileged }
+    }
+
+    pub fn is_required_for(&self, user: &User) -> bool {
+        self.enforce_privileged && user.has_any_role(&[Role::Admin, Role::Director])
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TotpStatus {
+    Disabled,
+    // Secret generated but never confirmed with a valid code
+    Pending,
+    Enabled,
+}
+
+// Returned once at enrollment; neither the secret URI nor the recovery codes
+// can be retrieved again afterwards.
+#[derive(Serialize)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct TotpRow {
+    secret: String,
+    confirmed_at: Option<DateTime<Utc>>,
+    last_used_step: Option<i64>,
+}
+
+fn build_totp(secret_base32: &str, account: &str) -> Option<TOTP> {
+    let secret = Secret::Encoded(secret_base32.to_string()).to_bytes().ok()?;
+    Some(TOTP::new_unchecked(Algorithm::SHA1, DIGITS, 1, STEP, secret, Some(ISSUER.to_string()), account.to_string()))
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Time step (current one, or one step of clock skew either way) the code is valid for.
+fn matching_step(totp: &TOTP, code: &str) -> Option<i64> {
+    let current = (Utc::now().timestamp() as u64) / STEP;
+    [current - 1, current, current + 1]
+        .into_iter()
+        .find(|step| constant_time_eq(&totp.generate(step * STEP), code))
+        .map(|step| step as i64)
+}
+
+fn generate_recovery_code() -> String {
+    let raw: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(RECOVERY_CODE_LEN)
+        .map(|c| char::from(c).to_ascii_lowercase())
+        .collect();
+    format!("{}-{}", &raw[..RECOVERY_CODE_LEN / 2], &raw[RECOVERY_CODE_LEN / 2..])
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+pub async fn status(pool: &PgPool, user_id: Uuid) -> Result<TotpStatus, sqlx::Error> {
+    let confirmed: Option<Option<DateTime<Utc>>> =
+        sqlx::query_scalar("SELECT confirmed_at FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(match confirmed {
+        None => TotpStatus::Disabled,
+        Some(None) => TotpStatus::Pending,
+        Some(Some(_)) => TotpStatus::Enabled,
+    })
+}
+
+// Generates a new secret and recovery codes. Replaces a pending enrollment but
+// returns None if TOTP is already enabled for the user.
+pub async fn start_enrollment(pool: &PgPool, user: &User) -> Result<Option<TotpEnrollment>, sqlx::Error> {
+    let secret = Secret::generate_secret().to_encoded().to_string();
+    let Some(totp) = build_totp(&secret, &user.email) else {
+        return Err(sqlx::Error::Protocol("generated TOTP secret could not be decoded".into()));
+    };
+
+    let mut tx = pool.begin().await?;
+
+    let stored = sqlx::query(
+        r#"
+        INSERT INTO user_totp (user_id, secret) VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE
+            SET secret = EXCLUDED.secret, last_used_step = NULL, created_at = now()
+            WHERE user_totp.confirmed_at IS NULL
+        "#
+    )
+    .bind(user.id)
+    .bind(&secret)
+    .execute(&mut *tx)
+    .await?;
+    if stored.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?;
+
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+    for code in &recovery_codes {
+        sqlx::query("INSERT INTO totp_recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+            .bind(user.id)
+            .bind(hash_token(&normalize_recovery_code(code)))
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(Some(TotpEnrollment { secret, otpauth_uri: totp.get_url(), recovery_codes }))
+}
+
+// Checks a TOTP code. A code is accepted at most once (its time step must be newer
+// than the last accepted one); the first valid code confirms a pending enrollment.
+pub async fn verify_code(pool: &PgPool, user: &User, code: &str) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query_as::<_, TotpRow>(
+        "SELECT secret, confirmed_at, last_used_step FROM user_totp WHERE user_id = $1"
+    )
+    .bind(user.id)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(false);
+    };
+    let Some(step) = build_totp(&row.secret, &user.email).and_then(|totp| matching_step(&totp, code.trim())) else {
+        return Ok(false);
+    };
+    if row.last_used_step.is_some_and(|last| step <= last) {
+        return Ok(false);
+    }
+
+    let updated = sqlx::query(
+        r#"
+        UPDATE user_totp
+        SET last_used_step = $2, confirmed_at = COALESCE(confirmed_at, now())
+        WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+        "#
+    )
+    .bind(user.id)
+    .bind(step)
+    .execute(pool)
+    .await?;
+
+    if row.confirmed_at.is_none() && updated.rows_affected() > 0 {
+        tracing::info!(user_id = %user.id, "two-factor authentication enabled");
+    }
+    Ok(updated.rows_affected() > 0)
+}
+
+// Consumes one of the user's unused recovery codes. Only valid once TOTP is enabled.
+pub async fn use_recovery_code(pool: &PgPool, user_id: Uuid, code: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE totp_recovery_codes SET used_at = now()
+        WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+          AND EXISTS (SELECT 1 FROM user_totp WHERE user_id = $1 AND confirmed_at IS NOT NULL)
+        "#
+    )
+    .bind(user_id)
+    .bind(hash_token(&normalize_recovery_code(code)))
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn disable(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    let result = sqlx::query("DELETE FROM user_totp WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::insert_user;
+
+    // The code of the enrollment's secret for a time step `offset` steps from now
+    fn code(enrollment: &TotpEnrollment, user: &User, offset: i64) -> String {
+        let step = (Utc::now().timestamp() as u64 / STEP) as i64 + offset;
+        build_totp(&enrollment.secret, &user.email).unwrap().generate(step as u64 * STEP)
+    }
+
+    async fn enrolled_user(pool: &PgPool) -> (User, TotpEnrollment) {
+        let user = insert_user(pool, "a@example.com", &[Role::Director], None).await;
+        let enrollment = start_enrollment(pool, &user).await.unwrap().unwrap();
+        (user, enrollment)
+    }
+
+    #[sqlx::test]
+    async fn enrollment_stays_pending_until_confirmed(pool: PgPool) {
+        let (user, enrollment) = enrolled_user(&pool).await;
+        assert_eq!(status(&pool, user.id).await.unwrap(), TotpStatus::Pending);
+
+        // A code from outside the allowed clock skew
+        assert!(!verify_code(&pool, &user, &code(&enrollment, &user, 10)).await.unwrap());
+        assert!(!use_recovery_code(&pool, user.id, &enrollment.recovery_codes[0]).await.unwrap());
+        assert_eq!(status(&pool, user.id).await.unwrap(), TotpStatus::Pending);
+
+        // Enrolling again while pending replaces the secret
+        let enrollment = start_enrollment(&pool, &user).await.unwrap().unwrap();
+        assert!(verify_code(&pool, &user, &code(&enrollment, &user, 0)).await.unwrap());
+        assert_eq!(status(&pool, user.id).await.unwrap(), TotpStatus::Enabled);
+        assert!(start_enrollment(&pool, &user).await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn codes_cannot_be_replayed(pool: PgPool) {
+        let (user, enrollment) = enrolled_user(&pool).await;
+        let current = code(&enrollment, &user, 0);
+        assert!(verify_code(&pool, &user, &current).await.unwrap());
+        assert!(!verify_code(&pool, &user, &current).await.unwrap());
+        // Nor can an older code that is still within the allowed clock skew
+        assert!(!verify_code(&pool, &user, &code(&enrollment, &user, -1)).await.unwrap());
+        assert!(verify_code(&pool, &user, &code(&enrollment, &user, 1)).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn recovery_codes_work_once(pool: PgPool) {
+        let (user, enrollment) = enrolled_user(&pool).await;
+        assert!(verify_code(&pool, &user, &code(&enrollment, &user, 0)).await.unwrap());
+
+        let first = &enrollment.recovery_codes[0];
+        assert!(use_recovery_code(&pool, user.id, &first.to_uppercase().replace('-', "")).await.unwrap());
+        assert!(!use_recovery_code(&pool, user.id, first).await.unwrap());
+        assert!(use_recovery_code(&pool, user.id, &enrollment.recovery_codes[1]).await.unwrap());
+        assert!(!use_recovery_code(&pool, user.id, "aaaaa-bbbbb").await.unwrap());
+    }
+}
@@ -5,6 +5,7 @@ export interface Absence {
   student_id: string;
   date: string;
   reason: string;
+  term_id?: string | null;
   created_at: string;
 }
 
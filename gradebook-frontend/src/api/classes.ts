@@ -0,0 +1,24 @@
+import api from './index';
+
+export interface SchoolClass {
+  id: string;
+  school_id: string;
+  grade_level: number;
+  letter: string;
+  academic_year_id: string;
+  homeroom_teacher_id: string | null;
+  created_at: string;
+}
+
+// "10A"
+export const className = (schoolClass: SchoolClass) => `${schoolClass.grade_level}${schoolClass.letter}`;
+
+export const getClasses = async (): Promise<SchoolClass[]> => {
+  const res = await api.get('/classes');
+  return res.data;
+};
+
+export const getClassRoster = async (id: string) => {
+  const res = await api.get(`/classes/${id}/students`);
+  return res.data;
+};
@@ -0,0 +1,59 @@
+import api from './index';
+
+export interface CurriculumEntry {
+  id: string;
+  class_id: string;
+  term_id: string;
+  subject_id: string;
+  weekly_hours: number | null;
+  teacher_ids: string[];
+  created_at: string;
+}
+
+export interface CurriculumParams {
+  class_id?: string;
+  term_id?: string;
+  teacher_id?: string;
+  school_id?: string;
+}
+
+export const getCurriculum = async (params: CurriculumParams = {}): Promise<CurriculumEntry[]> => {
+  const res = await api.get('/curriculum', { params });
+  return res.data;
+};
+
+export const createCurriculumEntry = async (entry: {
+  class_id: string;
+  term_id: string;
+  subject_id: string;
+  weekly_hours: number;
+  teacher_ids?: string[];
+}): Promise<CurriculumEntry> => {
+  const res = await api.post('/curriculum', entry);
+  return res.data;
+};
+
+export const updateCurriculumEntry = async (
+  id: string,
+  changes: { weekly_hours?: number; teacher_ids?: string[] }
+): Promise<CurriculumEntry> => {
+  const res = await api.put(`/curriculum/${id}`, changes);
+  return res.data;
+};
+
+export const deleteCurriculumEntry = async (id: string): Promise<void> => {
+  await api.delete(`/curriculum/${id}`);
+};
+
+export const copyCurriculum = async (
+  fromTermId: string,
+  toTermId: string,
+  classId?: string
+): Promise<CurriculumEntry[]> => {
+  const res = await api.post('/curriculum/copy', {
+    from_term_id: fromTermId,
+    to_term_id: toTermId,
+    class_id: classId,
+  });
+  return res.data;
+};
@@ -3,9 +3,11 @@ import api from './index';
 export interface Grade {
   id: string;
   student_id: string;
-  subject: string;
+  subject_id: string;
   value: number;
   teacher_id: string;
+  entered_by?: string | null;
+  term_id?: string | null;
   created_at: string;
 }
 
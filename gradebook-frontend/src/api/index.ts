@@ -4,15 +4,60 @@ const api = axios.create({
   baseURL: 'http://127.0.0.1:3000',
 });
 
+const isPublic = (url?: string) =>
+  !!url && (url.startsWith('/login') || ['/auth/refresh', '/auth/forgot', '/auth/reset', '/register'].includes(url));
+
 api.interceptors.request.use((config) => {
-  if (config.url && !config.url.endsWith('/login')) {
-    const userId = localStorage.getItem('user_id');
-    if (userId) {
+  if (!isPublic(config.url)) {
+    const token = localStorage.getItem('access_token');
+    if (token) {
       config.headers = config.headers || {};
-      config.headers['x-user-id'] = userId;
+      config.headers['Authorization'] = `Bearer ${token}`;
     }
   }
   return config;
 });
 
-export default api; 
\ No newline at end of file
+// Access tokens are short-lived: on a 401, swap the refresh token for a new pair once and retry.
+let refreshing: Promise<string | null> | null = null;
+
+const refreshAccessToken = async (): Promise<string | null> => {
+  const refreshToken = localStorage.getItem('refresh_token');
+  if (!refreshToken) return null;
+  try {
+    const res = await api.post('/auth/refresh', { refresh_token: refreshToken });
+    localStorage.setItem('access_token', res.data.access_token);
+    localStorage.setItem('refresh_token', res.data.refresh_token);
+    return res.data.access_token;
+  } catch {
+    localStorage.removeItem('access_token');
+    localStorage.removeItem('refresh_token');
+    return null;
+  }
+};
+
+api.interceptors.response.use(undefined, async (error) => {
+  const original = error.config;
+  if (error.response?.status === 401 && original && !original._retried && !isPublic(original.url)) {
+    original._retried = true;
+    refreshing = refreshing || refreshAccessToken().finally(() => { refreshing = null; });
+    const token = await refreshing;
+    if (token) {
+      original.headers['Authorization'] = `Bearer ${token}`;
+      return api(original);
+    }
+  }
+  return Promise.reject(error);
+});
+
+// Readable message for a failed request. Password policy rejections (422) list every violated rule.
+export const errorMessage = (err: any, fallback: string): string => {
+  const data = err?.response?.data;
+  if (data?.violations?.length) {
+    return data.violations.map((v: { message: string }) => v.message).join('. ');
+  }
+  if (typeof data === 'string' && data) return data;
+  return data?.error || data?.message || fallback;
+};
+
+export default api; 
@@ -10,12 +10,13 @@ export interface AbsenceCount {
   absence_count: number;
 }
 
-export const getAvgGrades = async (): Promise<AvgGrade[]> => {
-  const res = await api.get('/stats/avg_grade');
+// Without a term the figures cover all grades and absences
+export const getAvgGrades = async (termId?: string): Promise<AvgGrade[]> => {
+  const res = await api.get('/stats/avg_grade', { params: { term_id: termId } });
   return res.data;
 };
 
-export const getAbsenceCounts = async (): Promise<AbsenceCount[]> => {
-  const res = await api.get('/stats/absence_count');
+export const getAbsenceCounts = async (termId?: string): Promise<AbsenceCount[]> => {
+  const res = await api.get('/stats/absence_count', { params: { term_id: termId } });
   return res.data;
 }; 
\ No newline at end of file
@@ -3,8 +3,11 @@ import api from './index';
 export interface Student {
   id: string;
   user_id: string;
-  class: string;
+  class_id: string | null;
+  school_id?: string | null;
   created_at: string;
+  // Class name such as "10A" (when joined from backend)
+  class?: string | null;
   // User information fields (when joined from backend)
   first_name?: string;
   last_name?: string;
@@ -0,0 +1,18 @@
+import api from './index';
+
+export interface Subject {
+  id: string;
+  code: string;
+  names: Record<string, string>;
+  active: boolean;
+  created_at: string;
+}
+
+// Name in the preferred language, falling back to English, any name, then the code
+export const subjectName = (subject: Subject, language = 'en') =>
+  subject.names[language] || subject.names.en || Object.values(subject.names)[0] || subject.code;
+
+export const getSubjects = async (active?: boolean): Promise<Subject[]> => {
+  const res = await api.get('/subjects', { params: { active } });
+  return res.data;
+};
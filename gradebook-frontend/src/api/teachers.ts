@@ -0,0 +1,35 @@
+import api from './index';
+
+export interface TeacherProfile {
+  user_id: string;
+  first_name: string;
+  last_name: string;
+  email: string;
+  school_id: string | null;
+  phone: string | null;
+  address: string | null;
+  date_of_birth: string | null;
+  hired_on: string | null;
+  // Subjects the teacher is qualified to teach
+  subject_ids: string[];
+}
+
+export const getTeachers = async (): Promise<TeacherProfile[]> => {
+  const res = await api.get('/teachers');
+  return res.data;
+};
+
+export const getTeacher = async (id: string): Promise<TeacherProfile> => {
+  const res = await api.get(`/teachers/${id}`);
+  return res.data;
+};
+
+export const updateTeacher = async (id: string, profile: Partial<Pick<TeacherProfile, 'phone' | 'address' | 'date_of_birth' | 'hired_on'>>) => {
+  const res = await api.put(`/teachers/${id}`, profile);
+  return res.data;
+};
+
+export const setTeacherSubjects = async (id: string, subjectIds: string[]) => {
+  const res = await api.put(`/teachers/${id}/subjects`, { subject_ids: subjectIds });
+  return res.data;
+};
@@ -0,0 +1,29 @@
+import api from './index';
+
+export interface AcademicYear {
+  id: string;
+  school_id: string;
+  name: string;
+  starts_on: string;
+  ends_on: string;
+  created_at: string;
+}
+
+export interface Term {
+  id: string;
+  academic_year_id: string;
+  name: string;
+  starts_on: string;
+  ends_on: string;
+  created_at: string;
+}
+
+export const getAcademicYears = async (): Promise<AcademicYear[]> => {
+  const res = await api.get('/academic_years');
+  return res.data;
+};
+
+export const getTerms = async (academicYearId?: string): Promise<Term[]> => {
+  const res = await api.get('/terms', { params: { academic_year_id: academicYearId } });
+  return res.data;
+};
@@ -3,9 +3,10 @@ import api from './index';
 export interface User {
   id: string;
   email: string;
-  role: string;
+  roles: string[];
   first_name: string;
   last_name: string;
+  school_id?: string | null;
   created_at: string;
 }
 
@@ -27,4 +28,36 @@ export const updateUser = async (id: string, user: Partial<Omit<User, 'id' | 'cr
 export const deleteUser = async (id: string) => {
   const res = await api.delete(`/users/${id}`);
   return res.data;
-}; 
\ No newline at end of file
+}; 
+export interface UpdateMe {
+  email?: string;
+  first_name?: string;
+  last_name?: string;
+  current_password?: string;
+  new_password?: string;
+}
+
+export const getMe = async (): Promise<User> => {
+  const res = await api.get('/me');
+  return res.data;
+};
+
+export const updateMe = async (changes: UpdateMe): Promise<User> => {
+  const res = await api.put('/me', changes);
+  return res.data;
+};
+
+export interface Impersonation {
+  user_id: string;
+  role: string;
+  roles: string[];
+  impersonator_id: string;
+  access_token: string;
+  expires_at: string;
+}
+
+// Admin only: read-only session as another user ("view as")
+export const impersonateUser = async (id: string): Promise<Impersonation> => {
+  const res = await api.post(`/users/${id}/impersonate`);
+  return res.data;
+};
@@ -3,30 +3,32 @@ import { Box, TextField, Button, Dialog, DialogTitle, DialogContent, DialogActio
 import { Grade } from '../../api/grades';
 import { Student } from '../../api/students';
 import { User } from '../../api/users';
+import { Subject, subjectName } from '../../api/subjects';
+import { TeacherProfile } from '../../api/teachers';
 
 interface GradeFormProps {
   open: boolean;
   onClose: () => void;
-  onSubmit: (data: { student_id: string; subject: string; value: number; teacher_id: string }) => void;
+  onSubmit: (data: { student_id: string; subject_id: string; value: number; teacher_id: string }) => void;
   initialData?: Partial<Grade>;
   students: Student[];
   teachers: User[];
   users: User[];
+  subjects: Subject[];
+  teacherProfiles: TeacherProfile[];
   currentUserRole?: string;
   currentUserId?: string;
 }
 
-const subjects = ['Math', 'English', 'Science', 'History', 'Geography'];
-
-const GradeForm: React.FC<GradeFormProps> = ({ open, onClose, onSubmit, initialData, students, teachers, users, currentUserRole, currentUserId }) => {
+const GradeForm: React.FC<GradeFormProps> = ({ open, onClose, onSubmit, initialData, students, teachers, users, subjects, teacherProfiles, currentUserRole, currentUserId }) => {
   const [studentId, setStudentId] = useState(initialData?.student_id || '');
-  const [subject, setSubject] = useState(initialData?.subject || '');
+  const [subjectId, setSubjectId] = useState(initialData?.subject_id || '');
   const [value, setValue] = useState(initialData?.value?.toString() || '');
   const [teacherId, setTeacherId] = useState(initialData?.teacher_id || '');
 
   useEffect(() => {
     setStudentId(initialData?.student_id || '');
-    setSubject(initialData?.subject || '');
+    setSubjectId(initialData?.subject_id || '');
     setValue(initialData?.value?.toString() || '');
     
     // If editing, use the existing teacher_id, otherwise auto-set for teachers
@@ -41,11 +43,15 @@ const GradeForm: React.FC<GradeFormProps> = ({ open, onClose, onSubmit, initialD
 
   const handleSubmit = (e: React.FormEvent) => {
     e.preventDefault();
-    if (studentId && subject && value && teacherId) {
-      onSubmit({ student_id: studentId, subject, value: Number(value), teacher_id: teacherId });
+    if (studentId && subjectId && value && teacherId) {
+      onSubmit({ student_id: studentId, subject_id: subjectId, value: Number(value), teacher_id: teacherId });
     }
   };
 
+  // Only subjects the chosen teacher is qualified for, when their profile is known
+  const qualifiedSubjectIds = teacherProfiles.find(t => t.user_id === teacherId)?.subject_ids;
+  const isOffered = (subj: Subject) => subj.active && (!qualifiedSubjectIds || qualifiedSubjectIds.includes(subj.id));
+
   const getStudentName = (student: Student) => {
     if (student.first_name && student.last_name) {
       return `${student.first_name} ${student.last_name}`;
@@ -78,14 +84,15 @@ const GradeForm: React.FC<GradeFormProps> = ({ open, onClose, onSubmit, initialD
           <TextField
             select
             label="Subject"
-            value={subject}
-            onChange={e => setSubject(e.target.value)}
+            value={subjectId}
+            onChange={e => setSubjectId(e.target.value)}
             fullWidth
             margin="normal"
             required
           >
-            {subjects.map(subj => (
-              <MenuItem key={subj} value={subj}>{subj}</MenuItem>
+            {/* Subjects that can't take new grades stay visible on old ones */}
+            {subjects.filter(subj => isOffered(subj) || subj.id === subjectId).map(subj => (
+              <MenuItem key={subj.id} value={subj.id} disabled={!isOffered(subj)}>{subjectName(subj)}</MenuItem>
             ))}
           </TextField>
           <TextField
@@ -108,7 +115,7 @@ const GradeForm: React.FC<GradeFormProps> = ({ open, onClose, onSubmit, initialD
               margin="normal"
               required
             >
-              {teachers.filter(teacher => teacher.role === 'teacher').map(teacher => (
+              {teachers.filter(teacher => teacher.roles.includes('teacher')).map(teacher => (
                 <MenuItem key={teacher.id} value={teacher.id}>
                   {teacher.first_name} {teacher.last_name} ({teacher.email})
                 </MenuItem>
@@ -15,6 +15,7 @@ import DeleteIcon from '@mui/icons-material/Delete';
 import { Grade } from '../../api/grades';
 import { User } from '../../api/users';
 import { Student } from '../../api/students';
+import { Subject, subjectName } from '../../api/subjects';
 import { getStudentName, getTeacherName } from '../../utils/studentNames';
 
 interface GradeListProps {
@@ -24,11 +25,17 @@ interface GradeListProps {
   onDelete?: (grade: Grade) => void;
   users?: User[];
   students?: Student[];
+  subjects?: Subject[];
 }
 
-const GradeList: React.FC<GradeListProps> = ({ grades, role, onEdit, onDelete, users = [], students = [] }) => {
+const GradeList: React.FC<GradeListProps> = ({ grades, role, onEdit, onDelete, users = [], students = [], subjects = [] }) => {
   const canEdit = ["admin", "director", "teacher"].includes(role || '');
 
+  const getSubjectName = (subjectId: string) => {
+    const subject = subjects.find(s => s.id === subjectId);
+    return subject ? subjectName(subject) : subjectId;
+  };
+
   return (
     <Box sx={{ width: '100%' }}>
       <TableContainer component={Paper}>
@@ -49,7 +56,7 @@ const GradeList: React.FC<GradeListProps> = ({ grades, role, onEdit, onDelete, u
             {grades.map((grade) => (
               <TableRow key={grade.id}>
                 <TableCell>{getStudentName(grade.student_id, students, users)}</TableCell>
-                <TableCell>{grade.subject}</TableCell>
+                <TableCell>{getSubjectName(grade.subject_id)}</TableCell>
                 <TableCell>{grade.value}</TableCell>
                 <TableCell>{getTeacherName(grade.teacher_id, users)}</TableCell>
                 <TableCell>{grade.id}</TableCell>
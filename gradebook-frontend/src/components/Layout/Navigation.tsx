@@ -1,14 +1,14 @@
 import React from 'react';
-import { AppBar, Toolbar, Typography, Button, Box } from '@mui/material';
+import { AppBar, Toolbar, Typography, Button, Box, TextField, MenuItem } from '@mui/material';
 import { useNavigate, useLocation } from 'react-router-dom';
 import { useAuth } from '../../context/AuthContext';
 
 const Navigation: React.FC = () => {
-  const { role, logout } = useAuth();
+  const { role, roles, logout, switchRole, impersonating, stopImpersonation } = useAuth();
   const navigate = useNavigate();
   const location = useLocation();
 
-  if (location.pathname === '/login') return null;
+  if (['/login', '/register', '/reset-password'].includes(location.pathname)) return null;
 
   const getNavItems = () => {
     const baseItems = [
@@ -28,6 +28,7 @@ const Navigation: React.FC = () => {
       { label: role === 'student' ? 'My Grades' : 'Grades', path: '/grades', roles: ['admin', 'director', 'teacher', 'parent', 'student'] },
       { label: role === 'student' ? 'My Absences' : 'Absences', path: '/absences', roles: ['admin', 'director', 'teacher', 'parent', 'student'] },
       { label: 'Statistics', path: '/stats', roles: ['admin', 'director', 'teacher', 'parent', 'student'] },
+      { label: 'Profile', path: '/profile', roles: ['admin', 'director', 'teacher', 'parent', 'student'] },
     ];
 
     return [...baseItems, studentsItem, ...otherItems];
@@ -52,9 +53,29 @@ const Navigation: React.FC = () => {
                 {item.label}
               </Button>
             ))}
-          <Button color="inherit" onClick={logout} variant="outlined">
-            Logout ({role})
-          </Button>
+          {roles.length > 1 && (
+            <TextField
+              select
+              size="small"
+              value={role || ''}
+              onChange={e => { switchRole(e.target.value); navigate('/'); }}
+              sx={{ minWidth: 120, bgcolor: 'background.paper', borderRadius: 1 }}
+              inputProps={{ 'aria-label': 'Active role' }}
+            >
+              {roles.map(r => (
+                <MenuItem key={r} value={r}>{r}</MenuItem>
+              ))}
+            </TextField>
+          )}
+          {impersonating ? (
+            <Button color="warning" onClick={() => { stopImpersonation(); navigate('/users'); }} variant="contained">
+              Stop viewing as {role}
+            </Button>
+          ) : (
+            <Button color="inherit" onClick={logout} variant="outlined">
+              Logout ({role})
+            </Button>
+          )}
         </Box>
       </Toolbar>
     </AppBar>
@@ -42,7 +42,7 @@ const ParentStudentLinkForm: React.FC<ParentStudentLinkFormProps> = ({ open, onC
             margin="normal"
             required
           >
-            {parents.filter(parent => parent.role === 'parent').map(parent => (
+            {parents.filter(parent => parent.roles.includes('parent')).map(parent => (
               <MenuItem key={parent.id} value={parent.id}>
                 {parent.first_name} {parent.last_name} ({parent.email})
               </MenuItem>
@@ -2,19 +2,23 @@ import React, { useState, useEffect } from 'react';
 import { Box, TextField, Button, Dialog, DialogTitle, DialogContent, DialogActions, MenuItem } from '@mui/material';
 import { Student } from '../../api/students';
 import { User } from '../../api/users';
+import { SchoolClass, className } from '../../api/classes';
+import { AcademicYear } from '../../api/terms';
 
 interface StudentFormProps {
   open: boolean;
   onClose: () => void;
-  onSubmit: (data: { first_name: string; last_name: string; class: string; parent_id?: string }) => void;
+  onSubmit: (data: { first_name: string; last_name: string; class_id: string; parent_id?: string }) => void;
   initialData?: Partial<Student>;
   users: User[];
+  classes: SchoolClass[];
+  academicYears: AcademicYear[];
 }
 
-const StudentForm: React.FC<StudentFormProps> = ({ open, onClose, onSubmit, initialData, users }) => {
+const StudentForm: React.FC<StudentFormProps> = ({ open, onClose, onSubmit, initialData, users, classes, academicYears }) => {
   const [firstName, setFirstName] = useState('');
   const [lastName, setLastName] = useState('');
-  const [className, setClassName] = useState('');
+  const [classId, setClassId] = useState('');
   const [parentId, setParentId] = useState('');
 
   useEffect(() => {
@@ -23,30 +27,32 @@ const StudentForm: React.FC<StudentFormProps> = ({ open, onClose, onSubmit, init
       // For now, we'll just reset the form
       setFirstName('');
       setLastName('');
-      setClassName(initialData.class || '');
+      setClassId(initialData.class_id || '');
       setParentId('');
     } else {
       setFirstName('');
       setLastName('');
-      setClassName('');
+      setClassId('');
       setParentId('');
     }
   }, [initialData]);
 
   const handleSubmit = (e: React.FormEvent) => {
     e.preventDefault();
-    if (firstName && lastName && className) {
+    if (firstName && lastName && classId) {
       onSubmit({ 
         first_name: firstName, 
         last_name: lastName, 
-        class: className, 
+        class_id: classId, 
         parent_id: parentId || undefined 
       });
     }
   };
 
   // Filter users to only show those with role 'parent'
-  const parentUsers = users.filter(user => user.role === 'parent');
+  const parentUsers = users.filter(user => user.roles.includes('parent'));
+  const yearName = (schoolClass: SchoolClass) =>
+    academicYears.find(year => year.id === schoolClass.academic_year_id)?.name ?? '';
 
   return (
     <Dialog open={open} onClose={onClose}>
@@ -72,14 +78,21 @@ const StudentForm: React.FC<StudentFormProps> = ({ open, onClose, onSubmit, init
             helperText="Enter the student's last name"
           />
           <TextField
+            select
             label="Class"
-            value={className}
-            onChange={e => setClassName(e.target.value)}
+            value={classId}
+            onChange={e => setClassId(e.target.value)}
             fullWidth
             margin="normal"
             required
-            helperText="Enter the class for this student"
-          />
+            helperText="Select the class for this student"
+          >
+            {classes.map(schoolClass => (
+              <MenuItem key={schoolClass.id} value={schoolClass.id}>
+                {className(schoolClass)} ({yearName(schoolClass)})
+              </MenuItem>
+            ))}
+          </TextField>
           <TextField
             select
             label="Parent (Optional)"
@@ -5,31 +5,31 @@ import { User } from '../../api/users';
 interface UserFormProps {
   open: boolean;
   onClose: () => void;
-  onSubmit: (data: { first_name: string; last_name: string; email: string; role: string; password?: string }) => void;
+  onSubmit: (data: { first_name: string; last_name: string; email: string; roles: string[]; password?: string }) => void;
   initialData?: Partial<User>;
 }
 
-const roles = ['admin', 'director', 'teacher', 'parent', 'student'];
+const allRoles = ['admin', 'director', 'teacher', 'parent', 'student'];
 
 const UserForm: React.FC<UserFormProps> = ({ open, onClose, onSubmit, initialData }) => {
   const [firstName, setFirstName] = useState(initialData?.first_name || '');
   const [lastName, setLastName] = useState(initialData?.last_name || '');
   const [email, setEmail] = useState(initialData?.email || '');
-  const [role, setRole] = useState(initialData?.role || 'student');
+  const [roles, setRoles] = useState<string[]>(initialData?.roles || ['student']);
   const [password, setPassword] = useState('');
 
   useEffect(() => {
     setFirstName(initialData?.first_name || '');
     setLastName(initialData?.last_name || '');
     setEmail(initialData?.email || '');
-    setRole(initialData?.role || 'student');
+    setRoles(initialData?.roles || ['student']);
     setPassword('');
   }, [initialData]);
 
   const handleSubmit = (e: React.FormEvent) => {
     e.preventDefault();
-    if (firstName && lastName && email && role && (initialData ? true : password)) {
-      onSubmit({ first_name: firstName, last_name: lastName, email, role, ...(password ? { password } : {}) });
+    if (firstName && lastName && email && roles.length && (initialData ? true : password)) {
+      onSubmit({ first_name: firstName, last_name: lastName, email, roles, ...(password ? { password } : {}) });
     }
   };
 
@@ -65,14 +65,15 @@ const UserForm: React.FC<UserFormProps> = ({ open, onClose, onSubmit, initialDat
           />
           <TextField
             select
-            label="Role"
-            value={role}
-            onChange={e => setRole(e.target.value)}
+            label="Roles"
+            value={roles}
+            onChange={e => setRoles(typeof e.target.value === 'string' ? e.target.value.split(',') : e.target.value)}
+            SelectProps={{ multiple: true, renderValue: selected => (selected as string[]).join(', ') }}
             fullWidth
             margin="normal"
             required
           >
-            {roles.map(r => (
+            {allRoles.map(r => (
               <MenuItem key={r} value={r}>{r}</MenuItem>
             ))}
           </TextField>
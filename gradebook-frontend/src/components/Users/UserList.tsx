@@ -12,15 +12,17 @@ import {
 } from '@mui/material';
 import EditIcon from '@mui/icons-material/Edit';
 import DeleteIcon from '@mui/icons-material/Delete';
+import VisibilityIcon from '@mui/icons-material/Visibility';
 import { User } from '../../api/users';
 
 interface UserListProps {
   users: User[];
   onEdit?: (user: User) => void;
   onDelete?: (user: User) => void;
+  onImpersonate?: (user: User) => void;
 }
 
-const UserList: React.FC<UserListProps> = ({ users, onEdit, onDelete }) => {
+const UserList: React.FC<UserListProps> = ({ users, onEdit, onDelete, onImpersonate }) => {
   return (
     <Box sx={{ width: '100%' }}>
       <TableContainer component={Paper}>
@@ -39,7 +41,7 @@ const UserList: React.FC<UserListProps> = ({ users, onEdit, onDelete }) => {
               <TableRow key={user.id}>
                 <TableCell>{user.first_name} {user.last_name}</TableCell>
                 <TableCell>{user.email}</TableCell>
-                <TableCell>{user.role}</TableCell>
+                <TableCell>{user.roles.join(', ')}</TableCell>
                 <TableCell>{user.id}</TableCell>
                 <TableCell>
                   {onEdit && (
@@ -47,6 +49,11 @@ const UserList: React.FC<UserListProps> = ({ users, onEdit, onDelete }) => {
                       <EditIcon />
                     </IconButton>
                   )}
+                  {onImpersonate && !user.roles.includes('admin') && (
+                    <IconButton onClick={() => onImpersonate(user)} size="small" title="View as this user">
+                      <VisibilityIcon />
+                    </IconButton>
+                  )}
                   {onDelete && (
                     <IconButton onClick={() => onDelete(user)} size="small" color="error">
                       <DeleteIcon />
@@ -1,37 +1,110 @@
 import React, { createContext, useContext, useState, useEffect } from 'react';
+import api from '../api';
 
 interface AuthContextType {
   userId: string | null;
+  // The role the UI currently acts in; one of `roles`
   role: string | null;
-  login: (userId: string, role: string) => void;
+  roles: string[];
+  impersonating: boolean;
+  login: (userId: string, roles: string[], accessToken: string, refreshToken: string) => void;
   logout: () => void;
+  switchRole: (role: string) => void;
+  startImpersonation: (userId: string, roles: string[], accessToken: string) => void;
+  stopImpersonation: () => void;
 }
 
+// While an admin views the app as someone else, their own login is parked under these keys
+const ADMIN_KEYS = ['user_id', 'role', 'roles', 'access_token', 'refresh_token'];
+
+const storedRoles = (): string[] => {
+  try {
+    return JSON.parse(localStorage.getItem('roles') || '[]');
+  } catch {
+    return [];
+  }
+};
+const parked = (key: string) => `impersonator_${key}`;
+
 const AuthContext = createContext<AuthContextType | undefined>(undefined);
 
 export const AuthProvider: React.FC<{ children: React.ReactNode }> = ({ children }) => {
   const [userId, setUserId] = useState<string | null>(() => localStorage.getItem('user_id'));
   const [role, setRole] = useState<string | null>(() => localStorage.getItem('role'));
+  const [roles, setRoles] = useState<string[]>(storedRoles);
+  const [impersonating, setImpersonating] = useState(() => !!localStorage.getItem(parked('access_token')));
 
   useEffect(() => {
     if (userId) localStorage.setItem('user_id', userId);
     else localStorage.removeItem('user_id');
     if (role) localStorage.setItem('role', role);
     else localStorage.removeItem('role');
-  }, [userId, role]);
+    if (roles.length) localStorage.setItem('roles', JSON.stringify(roles));
+    else localStorage.removeItem('roles');
+  }, [userId, role, roles]);
 
-  const login = (userId: string, role: string) => {
+  // Tokens live only in localStorage so the API client can rotate them without a re-render
+  // Roles come most privileged first, so that one is active after login
+  const login = (userId: string, roles: string[], accessToken: string, refreshToken: string) => {
+    localStorage.setItem('access_token', accessToken);
+    localStorage.setItem('refresh_token', refreshToken);
     setUserId(userId);
-    setRole(role);
+    setRoles(roles);
+    setRole(roles[0] ?? null);
   };
 
   const logout = () => {
+    const accessToken = localStorage.getItem('access_token');
+    if (accessToken) {
+      api.post('/auth/logout', null, { headers: { Authorization: `Bearer ${accessToken}` } }).catch(() => undefined);
+    }
+    localStorage.removeItem('access_token');
+    localStorage.removeItem('refresh_token');
+    ADMIN_KEYS.forEach(key => localStorage.removeItem(parked(key)));
+    setImpersonating(false);
     setUserId(null);
+    setRoles([]);
     setRole(null);
   };
 
+  // Users with several roles (e.g. teacher and parent) pick which one the UI shows
+  const switchRole = (next: string) => {
+    if (roles.includes(next)) setRole(next);
+  };
+
+  // Impersonation sessions are read-only and can't be refreshed; they end on exit or expiry
+  const startImpersonation = (targetId: string, targetRoles: string[], accessToken: string) => {
+    ADMIN_KEYS.forEach(key => {
+      const value = localStorage.getItem(key);
+      if (value) localStorage.setItem(parked(key), value);
+    });
+    localStorage.setItem('access_token', accessToken);
+    localStorage.removeItem('refresh_token');
+    setImpersonating(true);
+    setUserId(targetId);
+    setRoles(targetRoles);
+    setRole(targetRoles[0] ?? null);
+  };
+
+  const stopImpersonation = () => {
+    const accessToken = localStorage.getItem('access_token');
+    if (accessToken) {
+      api.post('/auth/logout', null, { headers: { Authorization: `Bearer ${accessToken}` } }).catch(() => undefined);
+    }
+    ADMIN_KEYS.forEach(key => {
+      const value = localStorage.getItem(parked(key));
+      if (value) localStorage.setItem(key, value);
+      else localStorage.removeItem(key);
+      localStorage.removeItem(parked(key));
+    });
+    setImpersonating(false);
+    setUserId(localStorage.getItem('user_id'));
+    setRoles(storedRoles());
+    setRole(localStorage.getItem('role'));
+  };
+
   return (
-    <AuthContext.Provider value={{ userId, role, login, logout }}>
+    <AuthContext.Provider value={{ userId, role, roles, impersonating, login, logout, switchRole, startImpersonation, stopImpersonation }}>
       {children}
     </AuthContext.Provider>
   );
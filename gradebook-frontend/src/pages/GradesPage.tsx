@@ -4,6 +4,8 @@ import { useAuth } from '../context/AuthContext';
 import * as gradeApi from '../api/grades';
 import * as studentApi from '../api/students';
 import * as userApi from '../api/users';
+import * as subjectApi from '../api/subjects';
+import * as teacherApi from '../api/teachers';
 import GradeList from '../components/Grades/GradeList';
 import GradeForm from '../components/Grades/GradeForm';
 import { canAddGrades, canEditGrades, canDeleteGrades } from '../utils/rbac';
@@ -15,6 +17,8 @@ const GradesPage: React.FC = () => {
   const [students, setStudents] = useState<studentApi.Student[]>([]);
   const [teachers, setTeachers] = useState<userApi.User[]>([]);
   const [users, setUsers] = useState<userApi.User[]>([]);
+  const [subjects, setSubjects] = useState<subjectApi.Subject[]>([]);
+  const [teacherProfiles, setTeacherProfiles] = useState<teacherApi.TeacherProfile[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [formOpen, setFormOpen] = useState(false);
@@ -77,8 +81,9 @@ const GradesPage: React.FC = () => {
         const data = await userApi.getUsers();
         setUsers(data);
       } else {
-        // For non-admins, we'll build user info from students and other available data
-        setUsers([]);
+        // Non-admins can't list users, but always know themselves (e.g. a teacher's own name)
+        const me = await userApi.getMe();
+        setUsers([me]);
       }
     } catch (err: any) {
       console.error('Failed to load users:', err);
@@ -86,11 +91,38 @@ const GradesPage: React.FC = () => {
     }
   };
 
+  const fetchSubjects = async () => {
+    try {
+      const data = await subjectApi.getSubjects();
+      setSubjects(data);
+    } catch (err: any) {
+      console.error('Failed to load subjects:', err);
+    }
+  };
+
+  const fetchTeacherProfiles = async () => {
+    try {
+      // Staff pick any teacher; teachers only grade in their own name
+      if (role === 'admin' || role === 'director') {
+        setTeacherProfiles(await teacherApi.getTeachers());
+      } else if (role === 'teacher' && userId) {
+        setTeacherProfiles([await teacherApi.getTeacher(userId)]);
+      } else {
+        setTeacherProfiles([]);
+      }
+    } catch (err: any) {
+      console.error('Failed to load teacher profiles:', err);
+      setTeacherProfiles([]);
+    }
+  };
+
   useEffect(() => {
     fetchGrades();
     fetchStudents();
     fetchTeachers();
     fetchUsers();
+    fetchSubjects();
+    fetchTeacherProfiles();
     // eslint-disable-next-line
   }, [role, userId]);
 
@@ -117,7 +149,7 @@ const GradesPage: React.FC = () => {
     }
   };
 
-  const handleFormSubmit = async (data: { student_id: string; subject: string; value: number; teacher_id: string }) => {
+  const handleFormSubmit = async (data: { student_id: string; subject_id: string; value: number; teacher_id: string }) => {
     setActionLoading(true);
     try {
       if (editGrade) {
@@ -155,6 +187,7 @@ const GradesPage: React.FC = () => {
             onDelete={canDeleteGrades(role) ? handleDelete : undefined}
             users={users}
             students={students}
+            subjects={subjects}
           />
           <GradeForm
             open={formOpen}
@@ -164,6 +197,8 @@ const GradesPage: React.FC = () => {
             students={students}
             teachers={teachers}
             users={users}
+            subjects={subjects}
+            teacherProfiles={teacherProfiles}
             currentUserRole={role}
             currentUserId={userId}
           />
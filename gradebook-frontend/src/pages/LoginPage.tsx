@@ -1,27 +1,72 @@
 import React, { useState } from 'react';
-import { useNavigate, useLocation } from 'react-router-dom';
-import { Container, Typography, Box, Alert } from '@mui/material';
+import { useNavigate, useLocation, Link as RouterLink } from 'react-router-dom';
+import { Container, Typography, Box, Alert, TextField, Button, CircularProgress, Link } from '@mui/material';
 import LoginForm from '../components/Auth/LoginForm';
 import { useAuth } from '../context/AuthContext';
 import api from '../api';
 
+interface MfaChallenge {
+  mfa_token: string;
+  enrollment_required: boolean;
+}
+
+interface TotpEnrollment {
+  secret: string;
+  otpauth_uri: string;
+  recovery_codes: string[];
+}
+
 const LoginPage: React.FC = () => {
   const { login } = useAuth();
   const navigate = useNavigate();
   const location = useLocation();
   const [error, setError] = useState<string | null>(null);
   const [loading, setLoading] = useState(false);
+  const [challenge, setChallenge] = useState<MfaChallenge | null>(null);
+  const [enrollment, setEnrollment] = useState<TotpEnrollment | null>(null);
+  const [code, setCode] = useState('');
+
+  const finishLogin = (data: any) => {
+    login(data.user_id, data.roles, data.access_token, data.refresh_token);
+    const from = (location.state as any)?.from?.pathname || '/';
+    navigate(from, { replace: true });
+  };
 
   const handleLogin = async (email: string, password: string) => {
     setLoading(true);
     setError(null);
     try {
       const res = await api.post('/login', { email, password });
-      login(res.data.user_id, res.data.role);
-      const from = (location.state as any)?.from?.pathname || '/';
-      navigate(from, { replace: true });
+      if (res.data.mfa_required) {
+        setChallenge({ mfa_token: res.data.mfa_token, enrollment_required: res.data.enrollment_required });
+        if (res.data.enrollment_required) {
+          const enroll = await api.post('/login/totp/enroll', { mfa_token: res.data.mfa_token });
+          setEnrollment(enroll.data);
+        }
+      } else {
+        finishLogin(res.data);
+      }
+    } catch (err: any) {
+      setError(err.response?.data?.message || err.response?.data || 'Login failed');
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const handleCode = async (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!challenge || !code) return;
+    setLoading(true);
+    setError(null);
+    try {
+      // Codes with a dash are recovery codes, plain digits are TOTP codes
+      const body = code.includes('-')
+        ? { mfa_token: challenge.mfa_token, recovery_code: code }
+        : { mfa_token: challenge.mfa_token, code };
+      const res = await api.post('/login/totp', body);
+      finishLogin(res.data);
     } catch (err: any) {
-      setError(err.response?.data?.message || 'Login failed');
+      setError(err.response?.data?.message || err.response?.data || 'Invalid code');
     } finally {
       setLoading(false);
     }
@@ -32,10 +77,56 @@ const LoginPage: React.FC = () => {
       <Box mt={8}>
         <Typography variant="h4" align="center" gutterBottom>Login</Typography>
         {error && <Alert severity="error">{error}</Alert>}
-        <LoginForm onLogin={handleLogin} loading={loading} />
+        {!challenge && (
+          <>
+            <LoginForm onLogin={handleLogin} loading={loading} />
+            <Box mt={2} textAlign="center">
+              <Link component={RouterLink} to="/reset-password">Forgot password?</Link>
+              {' · '}
+              <Link component={RouterLink} to="/register">Have an invite code?</Link>
+            </Box>
+          </>
+        )}
+        {challenge && (
+          <form onSubmit={handleCode}>
+            {enrollment && (
+              <Box mt={2}>
+                <Alert severity="info">
+                  Two-factor authentication is required for your account. Add this key to your
+                  authenticator app, then enter the 6-digit code it shows.
+                </Alert>
+                <Typography variant="body2" sx={{ mt: 1, wordBreak: 'break-all' }}>{enrollment.otpauth_uri}</Typography>
+                <Typography variant="subtitle2" sx={{ mt: 1 }}>Key: {enrollment.secret}</Typography>
+                <Typography variant="subtitle2" sx={{ mt: 1 }}>Recovery codes (store them safely):</Typography>
+                <Typography variant="body2">{enrollment.recovery_codes.join(' ')}</Typography>
+              </Box>
+            )}
+            <TextField
+              label="Authentication code"
+              value={code}
+              onChange={e => setCode(e.target.value)}
+              fullWidth
+              margin="normal"
+              required
+              autoFocus
+            />
+            <Box mt={2}>
+              <Button
+                type="submit"
+                variant="contained"
+                color="primary"
+                fullWidth
+                disabled={loading}
+                startIcon={loading ? <CircularProgress size={20} /> : null}
+              >
+                {loading ? 'Verifying...' : 'Verify'}
+              </Button>
+            </Box>
+          </form>
+        )}
       </Box>
     </Container>
   );
 };
 
-export default LoginPage; 
\ No newline at end of file
+export default LoginPage;
@@ -0,0 +1,67 @@
+import React, { useState } from 'react';
+import { useSearchParams, Link as RouterLink } from 'react-router-dom';
+import { Container, Typography, Box, Alert, TextField, Button, Link } from '@mui/material';
+import api, { errorMessage } from '../api';
+
+// Without a token: ask for the account email. With ?token=...: choose a new password.
+const PasswordResetPage: React.FC = () => {
+  const [params] = useSearchParams();
+  const token = params.get('token');
+  const [email, setEmail] = useState('');
+  const [password, setPassword] = useState('');
+  const [message, setMessage] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [loading, setLoading] = useState(false);
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setLoading(true);
+    setError(null);
+    try {
+      if (token) {
+        await api.post('/auth/reset', { token, new_password: password });
+        setMessage('Your password has been changed. You can now log in.');
+      } else {
+        await api.post('/auth/forgot', { email });
+        setMessage('If an account exists for this email, a reset link has been sent.');
+      }
+    } catch (err: any) {
+      setError(errorMessage(err, 'Request failed'));
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <Container maxWidth="xs">
+      <Box mt={8}>
+        <Typography variant="h4" align="center" gutterBottom>
+          {token ? 'Choose a new password' : 'Forgot password'}
+        </Typography>
+        {error && <Alert severity="error">{error}</Alert>}
+        {message && <Alert severity="success">{message}</Alert>}
+        {!message && (
+          <form onSubmit={handleSubmit}>
+            {token ? (
+              <TextField label="New password" type="password" value={password}
+                onChange={e => setPassword(e.target.value)} fullWidth margin="normal" required />
+            ) : (
+              <TextField label="Email" type="email" value={email}
+                onChange={e => setEmail(e.target.value)} fullWidth margin="normal" required />
+            )}
+            <Box mt={2}>
+              <Button type="submit" variant="contained" color="primary" fullWidth disabled={loading}>
+                {token ? 'Change password' : 'Send reset link'}
+              </Button>
+            </Box>
+          </form>
+        )}
+        <Box mt={2} textAlign="center">
+          <Link component={RouterLink} to="/login">Back to login</Link>
+        </Box>
+      </Box>
+    </Container>
+  );
+};
+
+export default PasswordResetPage;
@@ -0,0 +1,92 @@
+import React, { useEffect, useState } from 'react';
+import { Box, Typography, Card, CardContent, TextField, Button, Alert, Grid } from '@mui/material';
+import * as userApi from '../api/users';
+import { errorMessage } from '../api';
+
+const ProfilePage: React.FC = () => {
+  const [me, setMe] = useState<userApi.User | null>(null);
+  const [form, setForm] = useState({ email: '', first_name: '', last_name: '' });
+  const [passwords, setPasswords] = useState({ current_password: '', new_password: '' });
+  const [message, setMessage] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    userApi.getMe()
+      .then(data => {
+        setMe(data);
+        setForm({ email: data.email, first_name: data.first_name, last_name: data.last_name });
+      })
+      .catch(() => setError('Failed to load your profile'));
+  }, []);
+
+  const submit = async (changes: userApi.UpdateMe, done: string) => {
+    setError(null);
+    setMessage(null);
+    try {
+      const updated = await userApi.updateMe(changes);
+      setMe(updated);
+      setPasswords({ current_password: '', new_password: '' });
+      setMessage(done);
+    } catch (err: any) {
+      setError(errorMessage(err, 'Update failed'));
+    }
+  };
+
+  const saveProfile = (e: React.FormEvent) => {
+    e.preventDefault();
+    const changes: userApi.UpdateMe = { first_name: form.first_name, last_name: form.last_name };
+    if (me && form.email !== me.email) {
+      changes.email = form.email;
+      changes.current_password = passwords.current_password;
+    }
+    submit(changes, 'Profile saved');
+  };
+
+  const changePassword = (e: React.FormEvent) => {
+    e.preventDefault();
+    submit(passwords, 'Password changed. Other devices have been logged out.');
+  };
+
+  return (
+    <Box mt={4}>
+      <Typography variant="h4" gutterBottom>My Profile</Typography>
+      {error && <Alert severity="error" sx={{ mb: 2 }}>{error}</Alert>}
+      {message && <Alert severity="success" sx={{ mb: 2 }}>{message}</Alert>}
+      <Grid container spacing={3}>
+        <Grid item xs={12} md={6}>
+          <Card>
+            <CardContent>
+              <form onSubmit={saveProfile}>
+                <TextField label="First name" value={form.first_name} fullWidth margin="normal"
+                  onChange={e => setForm({ ...form, first_name: e.target.value })} />
+                <TextField label="Last name" value={form.last_name} fullWidth margin="normal"
+                  onChange={e => setForm({ ...form, last_name: e.target.value })} />
+                <TextField label="Email" type="email" value={form.email} fullWidth margin="normal"
+                  helperText="Changing your email requires your current password"
+                  onChange={e => setForm({ ...form, email: e.target.value })} />
+                <Button type="submit" variant="contained" sx={{ mt: 2 }}>Save</Button>
+              </form>
+            </CardContent>
+          </Card>
+        </Grid>
+        <Grid item xs={12} md={6}>
+          <Card>
+            <CardContent>
+              <form onSubmit={changePassword}>
+                <TextField label="Current password" type="password" value={passwords.current_password}
+                  fullWidth margin="normal" required
+                  onChange={e => setPasswords({ ...passwords, current_password: e.target.value })} />
+                <TextField label="New password" type="password" value={passwords.new_password}
+                  fullWidth margin="normal" required
+                  onChange={e => setPasswords({ ...passwords, new_password: e.target.value })} />
+                <Button type="submit" variant="contained" sx={{ mt: 2 }}>Change password</Button>
+              </form>
+            </CardContent>
+          </Card>
+        </Grid>
+      </Grid>
+    </Box>
+  );
+};
+
+export default ProfilePage;
@@ -0,0 +1,65 @@
+import React, { useState } from 'react';
+import { useSearchParams, Link as RouterLink } from 'react-router-dom';
+import { Container, Typography, Box, Alert, TextField, Button, Link } from '@mui/material';
+import api, { errorMessage } from '../api';
+
+const RegisterPage: React.FC = () => {
+  const [params] = useSearchParams();
+  const [form, setForm] = useState({
+    code: params.get('code') || '',
+    email: '',
+    password: '',
+    first_name: '',
+    last_name: '',
+  });
+  const [done, setDone] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [loading, setLoading] = useState(false);
+
+  const update = (field: keyof typeof form) => (e: React.ChangeEvent<HTMLInputElement>) =>
+    setForm({ ...form, [field]: e.target.value });
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setLoading(true);
+    setError(null);
+    try {
+      await api.post('/register', form);
+      setDone(true);
+    } catch (err: any) {
+      setError(errorMessage(err, 'Registration failed'));
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <Container maxWidth="xs">
+      <Box mt={8}>
+        <Typography variant="h4" align="center" gutterBottom>Register</Typography>
+        {error && <Alert severity="error">{error}</Alert>}
+        {done ? (
+          <Alert severity="success">Your account has been created. You can now log in.</Alert>
+        ) : (
+          <form onSubmit={handleSubmit}>
+            <TextField label="Invite code" value={form.code} onChange={update('code')} fullWidth margin="normal" required />
+            <TextField label="First name" value={form.first_name} onChange={update('first_name')} fullWidth margin="normal" required />
+            <TextField label="Last name" value={form.last_name} onChange={update('last_name')} fullWidth margin="normal" required />
+            <TextField label="Email" type="email" value={form.email} onChange={update('email')} fullWidth margin="normal" required />
+            <TextField label="Password" type="password" value={form.password} onChange={update('password')} fullWidth margin="normal" required />
+            <Box mt={2}>
+              <Button type="submit" variant="contained" color="primary" fullWidth disabled={loading}>
+                Create account
+              </Button>
+            </Box>
+          </form>
+        )}
+        <Box mt={2} textAlign="center">
+          <Link component={RouterLink} to="/login">Back to login</Link>
+        </Box>
+      </Box>
+    </Container>
+  );
+};
+
+export default RegisterPage;
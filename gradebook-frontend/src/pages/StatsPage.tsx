@@ -1,9 +1,10 @@
 import React, { useEffect, useState } from 'react';
-import { Box, Typography, CircularProgress, Alert } from '@mui/material';
+import { Box, Typography, CircularProgress, Alert, TextField, MenuItem } from '@mui/material';
 import { useAuth } from '../context/AuthContext';
 import * as statsApi from '../api/stats';
 import * as studentApi from '../api/students';
 import * as userApi from '../api/users';
+import * as termApi from '../api/terms';
 import api from '../api';
 import StatsView from '../components/Stats/StatsView';
 
@@ -13,6 +14,8 @@ const StatsPage: React.FC = () => {
   const [absenceCounts, setAbsenceCounts] = useState<statsApi.AbsenceCount[]>([]);
   const [students, setStudents] = useState<studentApi.Student[]>([]);
   const [users, setUsers] = useState<userApi.User[]>([]);
+  const [terms, setTerms] = useState<termApi.Term[]>([]);
+  const [termId, setTermId] = useState('');
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
 
@@ -21,8 +24,8 @@ const StatsPage: React.FC = () => {
     setError(null);
     try {
       // Fetch all required data
-      let grades = await statsApi.getAvgGrades();
-      let absences = await statsApi.getAbsenceCounts();
+      let grades = await statsApi.getAvgGrades(termId || undefined);
+      let absences = await statsApi.getAbsenceCounts(termId || undefined);
       const studentsData = await studentApi.getStudents();
       setStudents(studentsData);
       
@@ -59,14 +62,31 @@ const StatsPage: React.FC = () => {
     }
   };
 
+  useEffect(() => {
+    termApi.getTerms().then(setTerms).catch(err => console.error('Failed to load terms:', err));
+  }, []);
+
   useEffect(() => {
     fetchStats();
     // eslint-disable-next-line
-  }, [role, userId]);
+  }, [role, userId, termId]);
 
   return (
     <Box mt={4}>
       <Typography variant="h4" gutterBottom>Statistics</Typography>
+      <TextField
+        select
+        label="Term"
+        value={termId}
+        onChange={e => setTermId(e.target.value)}
+        sx={{ mb: 2, minWidth: 240 }}
+        size="small"
+      >
+        <MenuItem value="">All terms</MenuItem>
+        {terms.map(term => (
+          <MenuItem key={term.id} value={term.id}>{term.name} ({term.starts_on} – {term.ends_on})</MenuItem>
+        ))}
+      </TextField>
       {loading && <CircularProgress />}
       {error && <Alert severity="error">{error}</Alert>}
       {!loading && !error && (
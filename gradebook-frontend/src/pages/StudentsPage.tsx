@@ -4,6 +4,8 @@ import { useAuth } from '../context/AuthContext';
 import * as studentApi from '../api/students';
 import * as userApi from '../api/users';
 import * as parentStudentApi from '../api/parentStudents';
+import * as classApi from '../api/classes';
+import * as termApi from '../api/terms';
 import StudentList from '../components/Students/StudentList';
 import StudentForm from '../components/Students/StudentForm';
 import { canAddStudents, canEditStudents, canDeleteStudents } from '../utils/rbac';
@@ -12,6 +14,8 @@ const StudentsPage: React.FC = () => {
   const { role, userId } = useAuth();
   const [students, setStudents] = useState<studentApi.Student[]>([]);
   const [users, setUsers] = useState<userApi.User[]>([]);
+  const [classes, setClasses] = useState<classApi.SchoolClass[]>([]);
+  const [academicYears, setAcademicYears] = useState<termApi.AcademicYear[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [formOpen, setFormOpen] = useState(false);
@@ -52,9 +56,21 @@ const StudentsPage: React.FC = () => {
     }
   };
 
+  const fetchClasses = async () => {
+    if (role === 'admin' || role === 'director') {
+      try {
+        setClasses(await classApi.getClasses());
+        setAcademicYears(await termApi.getAcademicYears());
+      } catch (err: any) {
+        console.error('Failed to load classes:', err);
+      }
+    }
+  };
+
   useEffect(() => {
     fetchStudents();
     fetchUsers();
+    fetchClasses();
     // eslint-disable-next-line
   }, [role, userId]);
 
@@ -81,7 +97,7 @@ const StudentsPage: React.FC = () => {
     }
   };
 
-  const handleFormSubmit = async (data: { first_name: string; last_name: string; class: string; parent_id?: string }) => {
+  const handleFormSubmit = async (data: { first_name: string; last_name: string; class_id: string; parent_id?: string }) => {
     setActionLoading(true);
     try {
       if (editStudent) {
@@ -89,13 +105,15 @@ const StudentsPage: React.FC = () => {
         // For now, we'll just show an error
         setError('Editing students is not yet implemented');
       } else {
-        // Create the user first
+        // Create the user first, in the school of the chosen class
+        const schoolClass = classes.find(c => c.id === data.class_id);
         const userData = {
           email: `${data.first_name.toLowerCase()}.${data.last_name.toLowerCase()}@school.com`,
           password: 'password123', // Default password
-          role: 'student',
+          roles: ['student'],
           first_name: data.first_name,
-          last_name: data.last_name
+          last_name: data.last_name,
+          school_id: schoolClass?.school_id
         };
         
         const newUser = await userApi.createUser(userData);
@@ -103,7 +121,7 @@ const StudentsPage: React.FC = () => {
         // Create the student record
         const studentData = {
           user_id: newUser.id,
-          class: data.class
+          class_id: data.class_id
         };
         
         const newStudent = await studentApi.createStudent(studentData);
@@ -205,6 +223,8 @@ const StudentsPage: React.FC = () => {
             onSubmit={handleFormSubmit}
             initialData={editStudent || undefined}
             users={users}
+            classes={classes}
+            academicYears={academicYears}
           />
         </>
       )}
@@ -1,6 +1,9 @@
 import React, { useEffect, useState } from 'react';
 import { Box, Typography, CircularProgress, Alert, Button, Stack } from '@mui/material';
 import * as userApi from '../api/users';
+import { errorMessage } from '../api';
+import { useNavigate } from 'react-router-dom';
+import { useAuth } from '../context/AuthContext';
 import UserList from '../components/Users/UserList';
 import UserForm from '../components/Users/UserForm';
 
@@ -11,6 +14,8 @@ const UsersPage: React.FC = () => {
   const [formOpen, setFormOpen] = useState(false);
   const [editUser, setEditUser] = useState<userApi.User | null>(null);
   const [actionLoading, setActionLoading] = useState(false);
+  const { startImpersonation } = useAuth();
+  const navigate = useNavigate();
 
   const fetchUsers = async () => {
     setLoading(true);
@@ -52,7 +57,20 @@ const UsersPage: React.FC = () => {
     }
   };
 
-  const handleFormSubmit = async (data: { first_name: string; last_name: string; email: string; role: string; password?: string }) => {
+  const handleImpersonate = async (user: userApi.User) => {
+    setActionLoading(true);
+    try {
+      const session = await userApi.impersonateUser(user.id);
+      startImpersonation(session.user_id, session.roles, session.access_token);
+      navigate('/');
+    } catch (err: any) {
+      setError(errorMessage(err, 'Failed to view as user'));
+    } finally {
+      setActionLoading(false);
+    }
+  };
+
+  const handleFormSubmit = async (data: { first_name: string; last_name: string; email: string; roles: string[]; password?: string }) => {
     setActionLoading(true);
     try {
       if (editUser) {
@@ -62,8 +80,8 @@ const UsersPage: React.FC = () => {
       }
       setFormOpen(false);
       await fetchUsers();
-    } catch {
-      setError('Failed to save user');
+    } catch (err: any) {
+      setError(errorMessage(err, 'Failed to save user'));
     } finally {
       setActionLoading(false);
     }
@@ -85,6 +103,7 @@ const UsersPage: React.FC = () => {
             users={users}
             onEdit={handleEdit}
             onDelete={handleDelete}
+            onImpersonate={handleImpersonate}
           />
           <UserForm
             open={formOpen}